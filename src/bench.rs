@@ -0,0 +1,139 @@
+use std::{
+    fmt::Display,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use crate::solution::Solution;
+
+/// Summary of the repeated timings of a single phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p99: Duration,
+}
+
+impl Stats {
+    /// Panics if `samples` is empty.
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let percentile = |p: usize| samples[(samples.len() - 1) * p / 100];
+        Stats {
+            min: samples[0],
+            median: percentile(50),
+            p99: percentile(99),
+        }
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            r#"{{"min_ns":{},"median_ns":{},"p99_ns":{}}}"#,
+            self.min.as_nanos(),
+            self.median.as_nanos(),
+            self.p99.as_nanos()
+        )
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {:>10.2?}  median {:>10.2?}  p99 {:>10.2?}",
+            self.min, self.median, self.p99
+        )
+    }
+}
+
+/// Timings of each phase of a day's solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timings {
+    pub day: u8,
+    pub iterations: usize,
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+impl Timings {
+    pub fn phases(&self) -> [(&'static str, Stats); 3] {
+        [
+            ("parse", self.parse),
+            ("part1", self.part1),
+            ("part2", self.part2),
+        ]
+    }
+
+    pub fn to_json(&self) -> String {
+        let phases: Vec<_> = self
+            .phases()
+            .iter()
+            .map(|(name, stats)| format!(r#""{name}":{}"#, stats.to_json()))
+            .collect();
+        format!(
+            r#"{{"day":{},"iterations":{},{}}}"#,
+            self.day,
+            self.iterations,
+            phases.join(",")
+        )
+    }
+}
+
+impl Display for Timings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "day {} ({} iterations)", self.day, self.iterations)?;
+        for (name, stats) in self.phases() {
+            write!(f, "\n    {name:<6} {stats}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A JSON array of the timings of every day, for comparing runs.
+pub fn to_json(timings: &[Timings]) -> String {
+    let days: Vec<_> = timings.iter().map(Timings::to_json).collect();
+    format!("[{}]", days.join(","))
+}
+
+/// Time `run`, `iterations` times.
+pub fn repeat(iterations: usize, mut run: impl FnMut()) -> Stats {
+    let samples = (0..iterations.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .collect();
+    Stats::from_samples(samples)
+}
+
+/// Time the parse and solve phases of `S` separately, `iterations` times each.
+pub fn bench<S: Solution>(input: &str, iterations: usize) -> Result<Timings, anyhow::Error> {
+    let iterations = iterations.max(1);
+    let mut parse = Vec::with_capacity(iterations);
+    let mut part1 = Vec::with_capacity(iterations);
+    let mut part2 = Vec::with_capacity(iterations);
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let parsed = black_box(S::parse(black_box(input))?);
+        parse.push(start.elapsed());
+
+        let start = Instant::now();
+        black_box(S::part1(&parsed)?);
+        part1.push(start.elapsed());
+
+        let start = Instant::now();
+        black_box(S::part2(&parsed)?);
+        part2.push(start.elapsed());
+    }
+
+    Ok(Timings {
+        day: S::DAY,
+        iterations,
+        parse: Stats::from_samples(parse),
+        part1: Stats::from_samples(part1),
+        part2: Stats::from_samples(part2),
+    })
+}
@@ -1,10 +1,19 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    fs::File,
+    io::Read,
     path::PathBuf,
+    str::FromStr,
 };
 
 use nohash_hasher::BuildNoHashHasher;
 
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+};
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ElfId(pub usize);
 
@@ -12,36 +21,66 @@ pub struct ElfId(pub usize);
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Calories(pub usize);
 
+#[derive(Debug)]
 pub struct CalorieList {
     pub map: HashMap<ElfId, Vec<Calories>, BuildNoHashHasher<usize>>,
-    pub calorie_totals: BTreeMap<Calories, ElfId>,
+    /// Every elf's total, sorted by calories. Elves with equal totals are all kept.
+    pub calorie_totals: BTreeSet<(Calories, ElfId)>,
 }
 
 impl CalorieList {
     pub fn top_n_calories(&self, n: usize) -> Calories {
-        let mut cal_iter = self.calorie_totals.iter().rev();
-        let mut total = 0;
-        for _ in 0..n {
-            total += cal_iter.next().unwrap().0 .0;
-        }
-        Calories(total)
+        let total = self.calorie_totals.iter().rev().take(n);
+        Calories(total.map(|(calories, _)| calories.0).sum())
     }
 
     pub fn load(file: impl Into<PathBuf>) -> Result<CalorieList, anyhow::Error> {
-        let data = std::fs::read_to_string(file.into())?;
+        Self::from_reader(File::open(file.into())?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalorieError {
+    InvalidCalories,
+}
+
+impl Display for CalorieError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CalorieError::InvalidCalories => write!(f, "expected a calorie count"),
+        }
+    }
+}
+
+impl FromStr for CalorieList {
+    type Err = ParseError<CalorieError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
         let mut map = HashMap::with_hasher(BuildNoHashHasher::default());
         let mut id = ElfId(0);
         let mut buffer = Vec::new();
-        for line in data.lines() {
-            if line.is_empty() {
+        for line in parse::lines(data) {
+            if line.text.is_empty() {
                 map.insert(id, buffer.clone());
                 id.0 += 1;
                 buffer.clear();
             } else {
-                buffer.push(Calories(line.parse()?))
+                let calories = line
+                    .text
+                    .parse()
+                    .map_err(|_| line.error(line.text, CalorieError::InvalidCalories))?;
+                buffer.push(Calories(calories))
             }
         }
-        let mut calorie_totals = BTreeMap::new();
+        // The last elf isn't necessarily followed by an empty line.
+        if !buffer.is_empty() {
+            map.insert(id, buffer);
+        }
+        let mut calorie_totals = BTreeSet::new();
         for entry in map.iter() {
             let calories = entry
                 .1
@@ -49,7 +88,7 @@ impl CalorieList {
                 .copied()
                 .reduce(|acc, e| Calories(acc.0 + e.0))
                 .unwrap_or_default();
-            calorie_totals.insert(calories, *entry.0);
+            calorie_totals.insert((calories, *entry.0));
         }
 
         Ok(CalorieList {
@@ -58,3 +97,81 @@ impl CalorieList {
         })
     }
 }
+
+pub struct CalorieCounting;
+
+impl Solution for CalorieCounting {
+    const DAY: u8 = 1;
+
+    type Input = CalorieList;
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error> {
+        Ok(input.parse()?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.top_n_calories(1).0)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.top_n_calories(3).0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+1000
+2000
+3000
+
+4000
+
+5000
+6000
+
+7000
+8000
+9000
+
+10000
+";
+
+    #[test]
+    fn example() {
+        let list: CalorieList = EXAMPLE.parse().unwrap();
+        assert_eq!(CalorieCounting::part1(&list).unwrap(), 24000);
+        assert_eq!(CalorieCounting::part2(&list).unwrap(), 45000);
+    }
+
+    #[test]
+    fn invalid_calories() {
+        let error = "100\n\n2OO\n".parse::<CalorieList>().unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 3, column: 1 });
+        assert_eq!(error.found, "2OO");
+    }
+
+    proptest! {
+        #[test]
+        fn top_n_matches_sorted_totals(
+            elves in prop::collection::vec(prop::collection::vec(0..10_000usize, 1..5), 1..20),
+            n in 0..25usize,
+        ) {
+            let data: Vec<String> = elves
+                .iter()
+                .map(|elf| elf.iter().map(|c| format!("{c}\n")).collect())
+                .collect();
+            let list: CalorieList = data.join("\n").parse().unwrap();
+
+            let mut totals: Vec<usize> = elves.iter().map(|elf| elf.iter().sum()).collect();
+            totals.sort_unstable_by(|a, b| b.cmp(a));
+            let expected: usize = totals.iter().take(n).sum();
+            prop_assert_eq!(list.top_n_calories(n).0, expected);
+        }
+    }
+}
@@ -0,0 +1,503 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+
+use crate::{
+    rps::{counter::Constraints, report::Format, Decoding},
+    runner::Part,
+    verify::ANSWERS_FILE,
+    warehouse::{
+        crane,
+        generate::{Generator, Heights},
+    },
+};
+
+pub const USAGE: &str = "\
+Usage: aoc22 <command> [options]
+
+Commands:
+    run       Solve puzzles and print the answers
+    verify    Check every solver against the known-correct answers
+    bench     Time the parse and solve phases of every day
+    crates    Rearrange the day 5 crates with a chosen crane, and print the top crates
+    generate  Write a random day 5 input, with moves that are all legal
+    rps       Play a rock paper scissors tournament between the built-in players, score or report
+              on a strategy guide, or find the best responses to the opponent's moves in it
+
+Run options:
+    --day <n>        Only run the given day
+    --part <1|2>     Only run the given part
+    --input <path>   Read the puzzle input from <path> instead of ./inputs (requires --day)
+
+Inputs missing from ./inputs are downloaded from $AOC_BASE_URL (default
+https://adventofcode.com/2022) using the session token in $AOC_SESSION, and cached.
+
+Verify options:
+    --answers <path>   Read the expected answers from <path> instead of ./answers.toml
+    --record           Overwrite the answers file with the current answers for ./inputs
+
+Bench options:
+    --day <n>            Only bench the given day
+    --iterations <n>     Number of times to repeat each phase (default 100)
+    --json               Print the timings as JSON
+
+Crates options:
+    --crane <model>    CrateMover9000 (default), CrateMover9001, capacity:<n> to lift at most
+                       <n> crates at a time, or chunks:<n> to turn over every <n> crates
+    --input <path>     Read the crates from <path> instead of ./inputs
+    --bench <n>        Also time copying and in-place execution of the moves <n> times each
+
+Generate options:
+    --seed <n>         Seed for the random scenario (default 0)
+    --stacks <n>       Number of stacks (default 9)
+    --heights <spec>   Starting stack heights: <n>, <min>-<max> (default 1-8), or pyramid:<peak>
+    --moves <n>        Number of moves (default 500)
+    --max-lift <n>     Most crates a single move takes (default a whole stack)
+    --output <path>    Write the input to <path> instead of printing it
+
+Rps options:
+    --rounds <n>       Rounds in each match (default 1000)
+    --seed <n>         Seed for the random player (default 0)
+    --input <path>     Strategy guide for the replaying player, instead of the day 2 input
+    --decoding <spec>  How to read the strategy guide: literal (default) for A/B/C and X/Y/Z as
+                       rock, paper and scissors, outcomes for X/Y/Z as lose, draw and win, or
+                       symbols for the opponent's moves, my moves and outcomes, like
+                       A=them:rock,B=them:paper,C=them:scissors,X=me:rock,Y=draw,Z=win
+    --game <path>      Play the tournament with the moves and rules of the game in <path>, a
+                       line per move with its points, a blank line, then lines like
+                       \"paper beats rock\" (default rock paper scissors)
+    --stream <n>       Instead of a tournament, score the strategy guide a line at a time as it's
+                       read, printing the totals so far every <n> rounds (0 for never)
+    --report <format>  Instead of a tournament, print statistics about the strategy guide as
+                       text, csv (a line per round) or json
+    --counter          Instead of a tournament, print the highest scoring responses to the
+                       opponent's moves in the strategy guide, as a strategy guide
+    --min-losses <n>   With --counter, lose at least <n> rounds
+    --max-run <n>      With --counter, play the same move at most <n> times in a row
+    --counts <r,p,s>   With --counter, play exactly <r> rocks, <p> papers and <s> scissors";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Run {
+        day: Option<u8>,
+        part: Option<Part>,
+        input: Option<PathBuf>,
+    },
+    Verify {
+        answers: PathBuf,
+        record: bool,
+    },
+    Bench {
+        day: Option<u8>,
+        iterations: usize,
+        json: bool,
+    },
+    Crates {
+        crane: String,
+        input: Option<PathBuf>,
+        bench: Option<usize>,
+    },
+    Generate {
+        generator: Generator,
+        output: Option<PathBuf>,
+    },
+    Rps {
+        rounds: usize,
+        seed: u64,
+        input: Option<PathBuf>,
+        /// How to read the strategy guide.
+        decoding: Decoding,
+        /// The game to play the tournament with, instead of rock paper scissors.
+        game: Option<PathBuf>,
+        /// Score the guide a line at a time, with progress every so many rounds, instead of playing
+        /// a tournament.
+        stream: Option<usize>,
+        /// Report on the guide instead of playing a tournament.
+        report: Option<Format>,
+        /// Find the best responses to the guide instead of playing a tournament.
+        counter: Option<Constraints>,
+    },
+    Help,
+}
+
+impl Command {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, anyhow::Error> {
+        let command = match args.next() {
+            Some(command) => command,
+            None => return Ok(Command::Help),
+        };
+        match command.as_str() {
+            "run" => {
+                let (mut day, mut part, mut input) = (None, None, None);
+                while let Some(flag) = args.next() {
+                    let mut value = || {
+                        args.next()
+                            .ok_or_else(|| anyhow!("Missing value for {flag}"))
+                    };
+                    match flag.as_str() {
+                        "--day" => day = Some(value()?.parse()?),
+                        "--part" => {
+                            part = Some(match value()?.as_str() {
+                                "1" => Part::One,
+                                "2" => Part::Two,
+                                other => return Err(anyhow!("Invalid part: {other}")),
+                            })
+                        }
+                        "--input" => input = Some(PathBuf::from(value()?)),
+                        _ => return Err(anyhow!("Unknown option: {flag}")),
+                    }
+                }
+                if input.is_some() && day.is_none() {
+                    return Err(anyhow!("--input requires --day"));
+                }
+                Ok(Command::Run { day, part, input })
+            }
+            "verify" => {
+                let mut answers = PathBuf::from(ANSWERS_FILE);
+                let mut record = false;
+                while let Some(flag) = args.next() {
+                    match flag.as_str() {
+                        "--answers" => {
+                            answers = args
+                                .next()
+                                .ok_or_else(|| anyhow!("Missing value for {flag}"))?
+                                .into()
+                        }
+                        "--record" => record = true,
+                        _ => return Err(anyhow!("Unknown option: {flag}")),
+                    }
+                }
+                Ok(Command::Verify { answers, record })
+            }
+            "bench" => {
+                let (mut day, mut iterations, mut json) = (None, 100, false);
+                while let Some(flag) = args.next() {
+                    let mut value = || {
+                        args.next()
+                            .ok_or_else(|| anyhow!("Missing value for {flag}"))
+                    };
+                    match flag.as_str() {
+                        "--day" => day = Some(value()?.parse()?),
+                        "--iterations" => iterations = value()?.parse()?,
+                        "--json" => json = true,
+                        _ => return Err(anyhow!("Unknown option: {flag}")),
+                    }
+                }
+                Ok(Command::Bench {
+                    day,
+                    iterations,
+                    json,
+                })
+            }
+            "crates" => {
+                let (mut crane, mut input, mut bench) =
+                    (String::from("CrateMover9000"), None, None);
+                while let Some(flag) = args.next() {
+                    let mut value = || {
+                        args.next()
+                            .ok_or_else(|| anyhow!("Missing value for {flag}"))
+                    };
+                    match flag.as_str() {
+                        "--crane" => crane = value()?,
+                        "--input" => input = Some(PathBuf::from(value()?)),
+                        "--bench" => bench = Some(value()?.parse()?),
+                        _ => return Err(anyhow!("Unknown option: {flag}")),
+                    }
+                }
+                crane::model(&crane)?;
+                Ok(Command::Crates {
+                    crane,
+                    input,
+                    bench,
+                })
+            }
+            "generate" => {
+                let (mut generator, mut output) = (Generator::new(0), None);
+                while let Some(flag) = args.next() {
+                    let mut value = || {
+                        args.next()
+                            .ok_or_else(|| anyhow!("Missing value for {flag}"))
+                    };
+                    match flag.as_str() {
+                        "--seed" => generator.seed = value()?.parse()?,
+                        "--stacks" => generator.stacks = value()?.parse()?,
+                        "--heights" => generator.heights = heights(&value()?)?,
+                        "--moves" => generator.moves = value()?.parse()?,
+                        "--max-lift" => generator.max_lift = Some(value()?.parse()?),
+                        "--output" => output = Some(PathBuf::from(value()?)),
+                        _ => return Err(anyhow!("Unknown option: {flag}")),
+                    }
+                }
+                if generator.stacks == 0 {
+                    return Err(anyhow!("--stacks must be at least 1"));
+                }
+                Ok(Command::Generate { generator, output })
+            }
+            "rps" => {
+                let (mut rounds, mut seed, mut input, mut game) = (1000, 0, None, None);
+                let (mut decoding, mut stream, mut report) =
+                    (Decoding::literal_moves(), None, None);
+                let (mut counter, mut constraints) = (false, Constraints::default());
+                while let Some(flag) = args.next() {
+                    let mut value = || {
+                        args.next()
+                            .ok_or_else(|| anyhow!("Missing value for {flag}"))
+                    };
+                    match flag.as_str() {
+                        "--rounds" => rounds = value()?.parse()?,
+                        "--seed" => seed = value()?.parse()?,
+                        "--input" => input = Some(PathBuf::from(value()?)),
+                        "--decoding" => decoding = Decoding::parse(&value()?)?,
+                        "--game" => game = Some(PathBuf::from(value()?)),
+                        "--stream" => stream = Some(value()?.parse()?),
+                        "--report" => report = Some(Format::parse(&value()?)?),
+                        "--counter" => counter = true,
+                        "--min-losses" => constraints.min_losses = value()?.parse()?,
+                        "--max-run" => constraints.max_run = Some(value()?.parse()?),
+                        "--counts" => constraints.counts = Some(counts(&value()?)?),
+                        _ => return Err(anyhow!("Unknown option: {flag}")),
+                    }
+                }
+                if !counter && constraints != Constraints::default() {
+                    return Err(anyhow!(
+                        "--min-losses, --max-run and --counts require --counter"
+                    ));
+                }
+                if [stream.is_some(), report.is_some(), counter]
+                    .into_iter()
+                    .filter(|&mode| mode)
+                    .count()
+                    > 1
+                {
+                    return Err(anyhow!(
+                        "Only one of --stream, --report and --counter can be used"
+                    ));
+                }
+                if game.is_some() && (stream.is_some() || report.is_some() || counter) {
+                    return Err(anyhow!(
+                        "--game only applies to the tournament, not --stream, --report or --counter"
+                    ));
+                }
+                Ok(Command::Rps {
+                    rounds,
+                    seed,
+                    input,
+                    decoding,
+                    game,
+                    stream,
+                    report,
+                    counter: counter.then_some(constraints),
+                })
+            }
+            "help" | "--help" | "-h" => Ok(Command::Help),
+            _ => Err(anyhow!("Unknown command: {command}")),
+        }
+    }
+}
+
+/// Parse `<n>`, `<min>-<max>` or `pyramid:<peak>`.
+fn heights(spec: &str) -> Result<Heights, anyhow::Error> {
+    if let Some(peak) = spec.strip_prefix("pyramid:") {
+        return Ok(Heights::Pyramid {
+            peak: peak.parse()?,
+        });
+    }
+    match spec.split_once('-') {
+        Some((min, max)) => {
+            let (min, max) = (min.parse()?, max.parse()?);
+            if min > max {
+                return Err(anyhow!("Invalid heights: {spec}"));
+            }
+            Ok(Heights::Uniform { min, max })
+        }
+        None => Ok(Heights::Fixed(spec.parse()?)),
+    }
+}
+
+/// Parse `<rocks>,<papers>,<scissors>`.
+fn counts(spec: &str) -> Result<[usize; 3], anyhow::Error> {
+    let counts: Vec<usize> = spec.split(',').map(str::parse).collect::<Result<_, _>>()?;
+    counts
+        .try_into()
+        .map_err(|_| anyhow!("Invalid counts: {spec}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Command, String> {
+        Command::parse(args.split_whitespace().map(String::from)).map_err(|e| e.to_string())
+    }
+
+    fn rps() -> Command {
+        Command::Rps {
+            rounds: 1000,
+            seed: 0,
+            input: None,
+            decoding: Decoding::literal_moves(),
+            game: None,
+            stream: None,
+            report: None,
+            counter: None,
+        }
+    }
+
+    #[test]
+    fn valid_commands() {
+        let with_rps = |change: fn(&mut Command)| {
+            let mut command = rps();
+            change(&mut command);
+            command
+        };
+        let cases = [
+            ("", Command::Help),
+            ("help", Command::Help),
+            (
+                "run",
+                Command::Run {
+                    day: None,
+                    part: None,
+                    input: None,
+                },
+            ),
+            (
+                "run --day 5 --part 2 --input crates.txt",
+                Command::Run {
+                    day: Some(5),
+                    part: Some(Part::Two),
+                    input: Some("crates.txt".into()),
+                },
+            ),
+            (
+                "verify --record",
+                Command::Verify {
+                    answers: ANSWERS_FILE.into(),
+                    record: true,
+                },
+            ),
+            (
+                "bench --day 2 --iterations 5 --json",
+                Command::Bench {
+                    day: Some(2),
+                    iterations: 5,
+                    json: true,
+                },
+            ),
+            (
+                "crates --crane capacity:3 --bench 10",
+                Command::Crates {
+                    crane: "capacity:3".into(),
+                    input: None,
+                    bench: Some(10),
+                },
+            ),
+            (
+                "generate --seed 7 --heights pyramid:4 --max-lift 2",
+                Command::Generate {
+                    generator: Generator::new(7)
+                        .with_heights(Heights::Pyramid { peak: 4 })
+                        .with_max_lift(2),
+                    output: None,
+                },
+            ),
+            ("rps", rps()),
+            (
+                "rps --rounds 10 --seed 3 --game rpsls.txt",
+                with_rps(|command| {
+                    if let Command::Rps {
+                        rounds, seed, game, ..
+                    } = command
+                    {
+                        (*rounds, *seed, *game) = (10, 3, Some("rpsls.txt".into()));
+                    }
+                }),
+            ),
+            (
+                "rps --decoding outcomes --report csv",
+                with_rps(|command| {
+                    if let Command::Rps {
+                        decoding, report, ..
+                    } = command
+                    {
+                        *decoding = Decoding::desired_outcomes();
+                        *report = Some(Format::Csv);
+                    }
+                }),
+            ),
+            (
+                "rps --stream 100",
+                with_rps(|command| {
+                    if let Command::Rps { stream, .. } = command {
+                        *stream = Some(100);
+                    }
+                }),
+            ),
+            (
+                "rps --counter --min-losses 2 --max-run 3 --counts 1,2,3",
+                with_rps(|command| {
+                    if let Command::Rps { counter, .. } = command {
+                        let constraints = Constraints::default()
+                            .with_min_losses(2)
+                            .with_max_run(3)
+                            .with_counts([1, 2, 3]);
+                        *counter = Some(constraints);
+                    }
+                }),
+            ),
+        ];
+        for (args, expected) in cases {
+            assert_eq!(parse(args).unwrap(), expected, "{args}");
+        }
+    }
+
+    #[test]
+    fn invalid_commands() {
+        let cases = [
+            ("fly", "Unknown command: fly"),
+            ("run --fast", "Unknown option: --fast"),
+            ("run --day", "Missing value for --day"),
+            ("run --part 3", "Invalid part: 3"),
+            ("run --input crates.txt", "--input requires --day"),
+            (
+                "crates --crane CrateMover9002",
+                "Unknown crane model: CrateMover9002",
+            ),
+            ("generate --stacks 0", "--stacks must be at least 1"),
+            ("generate --heights 5-2", "Invalid heights: 5-2"),
+            ("rps --report xml", "Invalid report format: xml"),
+            ("rps --counter --counts 1,2", "Invalid counts: 1,2"),
+            (
+                "rps --decoding A=them:rock,X=me:spock",
+                "Invalid decoding: unknown meaning spock",
+            ),
+            (
+                "rps --stream 10 --report text",
+                "Only one of --stream, --report and --counter can be used",
+            ),
+            (
+                "rps --report json --counter",
+                "Only one of --stream, --report and --counter can be used",
+            ),
+            (
+                "rps --min-losses 1",
+                "--min-losses, --max-run and --counts require --counter",
+            ),
+            (
+                "rps --max-run 2",
+                "--min-losses, --max-run and --counts require --counter",
+            ),
+            (
+                "rps --stream 10 --counts 1,1,1",
+                "--min-losses, --max-run and --counts require --counter",
+            ),
+            (
+                "rps --counter --game rpsls.txt",
+                "--game only applies to the tournament, not --stream, --report or --counter",
+            ),
+        ];
+        for (args, message) in cases {
+            assert_eq!(parse(args).unwrap_err(), message, "{args}");
+        }
+    }
+}
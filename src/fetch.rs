@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+
+use crate::runner::{Day, INPUT_DIR};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com/2022";
+
+/// Environment variable holding the session token sent with every request.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+/// Environment variable overriding [`DEFAULT_BASE_URL`].
+pub const BASE_URL_VAR: &str = "AOC_BASE_URL";
+
+/// Downloads puzzle inputs from `{base_url}/day/{day}/input`, caching them in `cache_dir`. A cached
+/// input is never fetched again.
+#[derive(Debug, Clone)]
+pub struct InputProvider {
+    pub base_url: String,
+    pub session: String,
+    pub cache_dir: PathBuf,
+}
+
+impl InputProvider {
+    pub fn new(base_url: impl Into<String>, session: impl Into<String>) -> Self {
+        InputProvider {
+            base_url: base_url.into(),
+            session: session.into(),
+            cache_dir: PathBuf::from(INPUT_DIR),
+        }
+    }
+
+    /// Configure the provider from [`SESSION_VAR`] and [`BASE_URL_VAR`].
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        let session = std::env::var(SESSION_VAR)
+            .map_err(|_| anyhow!("Set {SESSION_VAR} to your session token to download inputs"))?;
+        let base_url = std::env::var(BASE_URL_VAR).unwrap_or_else(|_| DEFAULT_BASE_URL.into());
+        Ok(InputProvider::new(base_url, session))
+    }
+
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = cache_dir.into();
+        self
+    }
+
+    /// The path of `day`'s cached input, downloading it first if it isn't cached yet.
+    pub fn input(&self, day: &Day) -> Result<PathBuf, anyhow::Error> {
+        let path = self.cache_dir.join(day.input);
+        if path.exists() {
+            return Ok(path);
+        }
+        let data = self.fetch(day.day)?;
+        std::fs::create_dir_all(&self.cache_dir)?;
+        // Write to a temporary file first, so an interrupted download is never mistaken for a
+        // cached input.
+        let partial = path.with_extension("part");
+        std::fs::write(&partial, data)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+
+    pub fn fetch(&self, day: u8) -> Result<String, anyhow::Error> {
+        let url = format!("{}/day/{day}/input", self.base_url.trim_end_matches('/'));
+        let response = ureq::get(&url)
+            .set("Cookie", &format!("session={}", self.session))
+            .set("User-Agent", concat!("aoc22/", env!("CARGO_PKG_VERSION")))
+            .call()
+            .map_err(|e| anyhow!("Failed to download the input for day {day}: {e}"))?;
+        Ok(response.into_string()?)
+    }
+}
+
+/// The default input of `day`, downloading it with a provider configured from the environment if
+/// it isn't in [`INPUT_DIR`].
+pub fn default_input(day: &Day) -> Result<PathBuf, anyhow::Error> {
+    let path = day.default_input();
+    if path.exists() {
+        return Ok(path);
+    }
+    InputProvider::from_env()?.input(day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::runner;
+
+    /// A stand-in puzzle server which answers every request with `status` and `body`, and records
+    /// the request line and cookie of each request it receives.
+    fn serve(status: &'static str, body: &'static str) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                for line in BufReader::new(&stream).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if !line.starts_with("GET") && !line.starts_with("Cookie") {
+                        continue;
+                    }
+                    request.push(line);
+                }
+                log.lock().unwrap().push(request.join("\n"));
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    /// A cache directory of its own for a test, removed again when the test is done with it.
+    struct CacheDir(PathBuf);
+
+    impl Drop for CacheDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn cache_dir(name: &str) -> CacheDir {
+        let dir = std::env::temp_dir().join(format!("aoc22-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        CacheDir(dir)
+    }
+
+    #[test]
+    fn downloads_once_then_uses_cache() {
+        let (url, requests) = serve("200 OK", "A Y\nB X\nC Z\n");
+        let dir = cache_dir("cache");
+        let provider = InputProvider::new(url, "token").with_cache_dir(&dir.0);
+        let day = runner::find_day(2).unwrap();
+
+        let path = provider.input(day).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "A Y\nB X\nC Z\n");
+        assert_eq!(provider.input(day).unwrap(), path);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(
+            *requests,
+            ["GET /day/2/input HTTP/1.1\nCookie: session=token"]
+        );
+    }
+
+    #[test]
+    fn never_fetches_cached_input() {
+        let (url, requests) = serve("200 OK", "");
+        let dir = cache_dir("cached");
+        std::fs::create_dir_all(&dir.0).unwrap();
+        std::fs::write(dir.0.join("calories.txt"), "1\n").unwrap();
+        let provider = InputProvider::new(url, "token").with_cache_dir(&dir.0);
+
+        let path = provider.input(runner::find_day(1).unwrap()).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "1\n");
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn failed_download_is_not_cached() {
+        let (url, _) = serve("400 Bad Request", "Please log in");
+        let dir = cache_dir("failed");
+        let provider = InputProvider::new(url, "expired").with_cache_dir(&dir.0);
+
+        assert!(provider.input(runner::find_day(6).unwrap()).is_err());
+        assert!(!dir.0.join("signal.txt").exists());
+    }
+}
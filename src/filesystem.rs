@@ -1,4 +1,11 @@
-use std::path::PathBuf;
+use std::{fmt::Display, fs::File, io::Read, path::PathBuf, str::FromStr};
+
+use anyhow::anyhow;
+
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+};
 
 #[derive(Debug)]
 pub struct Node {
@@ -48,12 +55,12 @@ impl Contents {
         }
     }
 
-    pub fn cd(&self, name: &str, arena: &[Node]) -> usize {
-        *self
-            .children()
-            .iter()
-            .find(|i| arena[**i].data.name() == name)
-            .unwrap()
+    /// The index of the child directory called `name`, if there is one.
+    pub fn cd(&self, name: &str, arena: &[Node]) -> Option<usize> {
+        self.children().iter().copied().find(|i| {
+            let child = &arena[*i].data;
+            matches!(child, Contents::Dir { .. }) && child.name() == name
+        })
     }
 }
 
@@ -65,63 +72,11 @@ pub struct Filesystem {
 
 impl Filesystem {
     pub fn load(file: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
-        let data = std::fs::read_to_string(file.into())?;
-
-        let root = Node {
-            parent: None,
-            data: Contents::Dir {
-                name: "/".into(),
-                children: Vec::new(),
-            },
-            size: 0,
-        };
-
-        let mut arena = vec![root];
-        let mut pointer = 0;
+        Self::from_reader(File::open(file.into())?)
+    }
 
-        let mut lines = data.lines().peekable();
-        while let Some(line) = lines.next() {
-            let mut words = line.split_whitespace();
-            match words.next().unwrap() {
-                "$" => match words.next().unwrap() {
-                    "cd" => match words.next().unwrap() {
-                        "/" => pointer = 0,
-                        ".." => pointer = arena[pointer].parent.unwrap(),
-                        name => pointer = arena[pointer].data.cd(name, &arena),
-                    },
-                    "ls" => {
-                        while !matches!(
-                            lines.peek().and_then(|l| l.split_whitespace().next()),
-                            Some("$")
-                        ) && lines.peek().is_some()
-                        {
-                            let line = lines.next().unwrap();
-                            let mut words = line.split_whitespace();
-                            let node = match words.next().unwrap() {
-                                "dir" => Node {
-                                    parent: Some(pointer),
-                                    data: Contents::new_dir(words.next().unwrap()),
-                                    size: 0,
-                                },
-                                size => Node {
-                                    parent: Some(pointer),
-                                    data: Contents::new_file(words.next().unwrap()),
-                                    size: size.parse().unwrap(),
-                                },
-                            };
-                            arena.push(node);
-                            let index = arena.len() - 1;
-                            arena[pointer].data.push_child(index);
-                        }
-                    }
-                    _ => unreachable!(),
-                },
-                _ => unreachable!(),
-            }
-        }
-        let mut fs = Filesystem { arena, pointer };
-        fs.calculate_size();
-        Ok(fs)
+    pub fn from_reader(reader: impl Read) -> Result<Self, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
     }
 
     pub fn calculate_size(&mut self) {
@@ -155,17 +110,266 @@ impl Filesystem {
         })
     }
 
-    pub fn dir_to_delete(&self, total_space: usize, space_needed: usize) -> &Node {
+    /// The smallest directory that frees up enough space to have `space_needed` out of
+    /// `total_space` available, or `None` if not even deleting everything would.
+    pub fn dir_to_delete(&self, total_space: usize, space_needed: usize) -> Option<&Node> {
         let used_space = self.arena[0].size;
-        let available_space = total_space - used_space;
-        let minimum_deletion = space_needed - available_space;
+        let minimum_deletion = (used_space + space_needed).saturating_sub(total_space);
+
+        self.arena
+            .iter()
+            .filter(|node| matches!(node.data, Contents::Dir { .. }))
+            .filter(|node| node.size >= minimum_deletion)
+            .min_by_key(|node| node.size)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilesystemError {
+    Command,
+    DirName,
+    ParentOfRoot,
+    UnknownDir,
+    Listing,
+    Name,
+}
+
+impl Display for FilesystemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilesystemError::Command => write!(f, "expected a command, `$ cd` or `$ ls`"),
+            FilesystemError::DirName => write!(f, "expected a directory to change to"),
+            FilesystemError::ParentOfRoot => write!(f, "expected to be below the root directory"),
+            FilesystemError::UnknownDir => write!(f, "expected a directory listed by `ls`"),
+            FilesystemError::Listing => write!(f, "expected `dir` or a file size"),
+            FilesystemError::Name => write!(f, "expected a name"),
+        }
+    }
+}
+
+impl FromStr for Filesystem {
+    type Err = ParseError<FilesystemError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let root = Node {
+            parent: None,
+            data: Contents::Dir {
+                name: "/".into(),
+                children: Vec::new(),
+            },
+            size: 0,
+        };
+
+        let mut arena = vec![root];
+        let mut pointer = 0;
 
-        let mut smallest_matching = &self.arena[0];
-        for node in self.arena.iter() {
-            if node.size > minimum_deletion && node.size < smallest_matching.size {
-                smallest_matching = node;
+        let mut lines = parse::lines(data).peekable();
+        while let Some(line) = lines.next() {
+            let mut words = line.text.split_whitespace();
+            line.expect_keyword(&mut words, "$", FilesystemError::Command)?;
+            match line.expect_word(&mut words, FilesystemError::Command)? {
+                "cd" => match line.expect_word(&mut words, FilesystemError::DirName)? {
+                    "/" => pointer = 0,
+                    name @ ".." => {
+                        pointer = arena[pointer]
+                            .parent
+                            .ok_or_else(|| line.error(name, FilesystemError::ParentOfRoot))?
+                    }
+                    name => {
+                        pointer = arena[pointer]
+                            .data
+                            .cd(name, &arena)
+                            .ok_or_else(|| line.error(name, FilesystemError::UnknownDir))?
+                    }
+                },
+                "ls" => {
+                    while let Some(line) =
+                        lines.next_if(|l| l.text.split_whitespace().next() != Some("$"))
+                    {
+                        let mut words = line.text.split_whitespace();
+                        let node = match line.expect_word(&mut words, FilesystemError::Listing)? {
+                            "dir" => Node {
+                                parent: Some(pointer),
+                                data: Contents::new_dir(
+                                    line.expect_word(&mut words, FilesystemError::Name)?,
+                                ),
+                                size: 0,
+                            },
+                            size => Node {
+                                parent: Some(pointer),
+                                data: Contents::new_file(
+                                    line.expect_word(&mut words, FilesystemError::Name)?,
+                                ),
+                                size: size
+                                    .parse()
+                                    .map_err(|_| line.error(size, FilesystemError::Listing))?,
+                            },
+                        };
+                        arena.push(node);
+                        let index = arena.len() - 1;
+                        arena[pointer].data.push_child(index);
+                    }
+                }
+                command => return Err(line.error(command, FilesystemError::Command)),
+            }
+        }
+        let mut fs = Filesystem { arena, pointer };
+        fs.calculate_size();
+        Ok(fs)
+    }
+}
+
+pub struct NoSpaceLeft;
+
+impl Solution for NoSpaceLeft {
+    const DAY: u8 = 7;
+
+    type Input = Filesystem;
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error> {
+        Ok(input.parse()?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.dirs_up_to(100_000).sum())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        input
+            .dir_to_delete(70_000_000, 30_000_000)
+            .map(|dir| dir.size)
+            .ok_or_else(|| anyhow!("No directory frees up enough space"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+";
+
+    /// The contents of a directory in a generated tree.
+    #[derive(Debug, Default)]
+    struct Listing {
+        dirs: Vec<String>,
+        files: Vec<usize>,
+    }
+
+    /// Files in random directories, rendered as a terminal session that lists every directory.
+    fn session() -> impl Strategy<Value = (String, BTreeMap<Vec<String>, Listing>)> {
+        let path = prop::collection::vec(prop::sample::select(vec!["a", "b", "c"]), 0..4);
+        prop::collection::vec((path, 1..100_000usize), 0..40).prop_map(|files| {
+            let mut tree: BTreeMap<Vec<String>, Listing> = BTreeMap::new();
+            tree.insert(Vec::new(), Listing::default());
+            for (path, size) in files {
+                let path: Vec<String> = path.into_iter().map(String::from).collect();
+                for depth in 1..=path.len() {
+                    let (parent, dir) = (&path[..depth - 1], &path[depth - 1]);
+                    if !tree.contains_key(&path[..depth]) {
+                        tree.insert(path[..depth].to_vec(), Listing::default());
+                        tree.get_mut(parent).unwrap().dirs.push(dir.clone());
+                    }
+                }
+                tree.get_mut(&path).unwrap().files.push(size);
+            }
+
+            let mut session = String::new();
+            for (path, listing) in tree.iter() {
+                session.push_str("$ cd /\n");
+                for dir in path {
+                    session.push_str(&format!("$ cd {dir}\n"));
+                }
+                session.push_str("$ ls\n");
+                for dir in listing.dirs.iter() {
+                    session.push_str(&format!("dir {dir}\n"));
+                }
+                for (i, size) in listing.files.iter().enumerate() {
+                    session.push_str(&format!("{size} file{i}\n"));
+                }
             }
+            (session, tree)
+        })
+    }
+
+    #[test]
+    fn example() {
+        let filesystem: Filesystem = EXAMPLE.parse().unwrap();
+        assert_eq!(NoSpaceLeft::part1(&filesystem).unwrap(), 95437);
+        assert_eq!(NoSpaceLeft::part2(&filesystem).unwrap(), 24933642);
+    }
+
+    #[test]
+    fn plenty_of_space() {
+        let filesystem: Filesystem = "$ cd /\n$ ls\n10 a\n".parse().unwrap();
+        assert_eq!(NoSpaceLeft::part2(&filesystem).unwrap(), 10);
+        assert!(filesystem.dir_to_delete(100, 200).is_none());
+    }
+
+    #[test]
+    fn unknown_dir() {
+        let error = "$ cd /\n$ ls\n10 a\n$ cd a\n"
+            .parse::<Filesystem>()
+            .unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 4, column: 6 });
+        assert_eq!(error.kind, FilesystemError::UnknownDir);
+    }
+
+    proptest! {
+        #[test]
+        fn sizes_match_path_prefix_sums(
+            (session, tree) in session(),
+            free in 0..200_000usize,
+            needed in 0..200_000usize,
+        ) {
+            let filesystem: Filesystem = session.parse().unwrap();
+
+            let size = |dir: &[String]| -> usize {
+                tree.iter()
+                    .filter(|(path, _)| path.starts_with(dir))
+                    .flat_map(|(_, listing)| listing.files.iter())
+                    .sum()
+            };
+            let mut expected: Vec<usize> = tree.keys().map(|dir| size(dir)).collect();
+            expected.sort_unstable();
+            let mut sizes: Vec<usize> = filesystem.dirs_up_to(usize::MAX).collect();
+            sizes.sort_unstable();
+            prop_assert_eq!(&sizes, &expected);
+
+            // Anywhere from nothing to delete to more than everything.
+            let total = size(&[]) + free;
+            let minimum_deletion = needed.saturating_sub(free);
+            let smallest = expected.iter().find(|&&s| s >= minimum_deletion);
+            let deleted = filesystem.dir_to_delete(total, needed).map(|dir| dir.size);
+            prop_assert_eq!(deleted.as_ref(), smallest);
         }
-        smallest_matching
     }
 }
@@ -1,56 +1,194 @@
+use std::{fs::File, hint::black_box, io::BufReader};
+
+use anyhow::anyhow;
+
 use crate::{
-    calories::CalorieList,
-    filesystem::Filesystem,
-    rps::Strategy,
-    rucksack::RucksackInventory,
-    sections::Sections,
-    signal::Signal,
-    warehouse::{Crane, Crates},
+    cli::{Command, USAGE},
+    rps::{
+        counter,
+        engine::{self, Cyclic, FrequencyCounter, Player, RandomPlayer, Replay, WinStayLoseShift},
+        variant::Game,
+        Move, RockPaperScissors, Strategy,
+    },
+    runner::{Part, DAYS},
+    solution::Solution,
+    verify::Answers,
+    warehouse::{crane, Crates, InvalidMoves, SupplyStacks},
 };
 
+pub mod bench;
 pub mod calories;
+pub mod cli;
+pub mod fetch;
 pub mod filesystem;
+pub mod parse;
+pub mod rng;
 pub mod rps;
 pub mod rucksack;
+pub mod runner;
 pub mod sections;
 pub mod signal;
+pub mod solution;
+pub mod verify;
 pub mod warehouse;
 
 fn main() -> Result<(), anyhow::Error> {
-    // Day 1
-    let calories = CalorieList::load("./inputs/calories.txt")?;
-    dbg!(calories.top_n_calories(1).0);
-    dbg!(calories.top_n_calories(3).0);
-
-    // Day 2
-    dbg!(Strategy::load_incorrect("./inputs/rps_strategy.txt")?.run_and_score());
-    dbg!(Strategy::load("./inputs/rps_strategy.txt")?.run_and_score());
-
-    // Day 3
-    let inventory = RucksackInventory::load("./inputs/rucksack_list.txt")?;
-    let analysis = inventory.analyze_rucksack();
-    dbg!(inventory.sum_priorities(analysis.errors));
-    dbg!(inventory.sum_priorities(analysis.badges));
-
-    // Day 4
-    let sections = Sections::load("./inputs/sections.txt")?;
-    dbg!(sections.fully_contained().count());
-    dbg!(sections.overlapped().count());
-
-    // Day 5
-    let crates = Crates::load("./inputs/crates.txt")?;
-    dbg!(crates.execute_moves(Crane::CrateMover9000).topmost());
-    dbg!(crates.execute_moves(Crane::CrateMover9001).topmost());
-
-    // Day 6
-    let signal = Signal::load("./inputs/signal.txt")?;
-    dbg!(signal.markers(4).next().unwrap());
-    dbg!(signal.markers(14).next().unwrap());
-
-    // Day 7
-    let filesystem = Filesystem::load("./inputs/filesystem.txt")?;
-    dbg!(filesystem.dirs_up_to(100_000).sum::<usize>());
-    dbg!(filesystem.dir_to_delete(70_000_000, 30_000_000).size);
+    match Command::parse(std::env::args().skip(1))? {
+        Command::Run { day, part, input } => {
+            let days = match day {
+                Some(day) => std::slice::from_ref(runner::find_day(day)?),
+                None => DAYS,
+            };
+            let parts = match part {
+                Some(part) => vec![part],
+                None => Part::ALL.to_vec(),
+            };
+            for day in days {
+                let input = match &input {
+                    Some(input) => input.clone(),
+                    None => fetch::default_input(day)?,
+                };
+                for &part in parts.iter() {
+                    let result = runner::run(day, part, &input)?;
+                    println!("{}", result.answer);
+                    eprintln!(
+                        "day {} part {}: {:?}",
+                        day.day,
+                        part.number(),
+                        result.elapsed
+                    );
+                }
+            }
+        }
+        Command::Verify { answers, record } => {
+            if record {
+                std::fs::write(&answers, Answers::record(DAYS)?.to_string())?;
+                eprintln!("recorded answers to {}", answers.display());
+                return Ok(());
+            }
+            let checks = verify::verify(&Answers::load(&answers)?);
+            for check in checks.iter() {
+                println!("{check}");
+            }
+            let failed = checks.iter().filter(|check| !check.passed()).count();
+            if failed > 0 {
+                return Err(anyhow!("{failed} of {} answers failed", checks.len()));
+            }
+        }
+        Command::Bench {
+            day,
+            iterations,
+            json,
+        } => {
+            let days = match day {
+                Some(day) => std::slice::from_ref(runner::find_day(day)?),
+                None => DAYS,
+            };
+            let mut timings = Vec::new();
+            for day in days {
+                let input = std::fs::read_to_string(fetch::default_input(day)?)?;
+                let timing = (day.bench)(&input, iterations)?;
+                if !json {
+                    println!("{timing}");
+                }
+                timings.push(timing);
+            }
+            if json {
+                println!("{}", bench::to_json(&timings));
+            }
+        }
+        Command::Crates {
+            crane,
+            input,
+            bench,
+        } => {
+            let input = match input {
+                Some(input) => input,
+                None => fetch::default_input(runner::find_day(SupplyStacks::DAY)?)?,
+            };
+            let crane = crane::model(&crane)?;
+            let crates = Crates::load(input)?;
+            let state = crates.try_execute_moves(&crane, InvalidMoves::Reject)?;
+            println!("{}", state.topmost());
 
+            // Every move is known to be legal now, so neither way of executing them will panic.
+            if let Some(iterations) = bench {
+                let copying = bench::repeat(iterations, || {
+                    black_box(crates.execute_moves(&crane));
+                });
+                let in_place = bench::repeat(iterations, || {
+                    let mut state = black_box(crates.clone());
+                    state.execute_moves_in_place(&crane);
+                    black_box(state);
+                });
+                eprintln!("copying  {copying}");
+                eprintln!("in place {in_place}");
+            }
+        }
+        Command::Generate { generator, output } => {
+            let crates = generator.generate();
+            match output {
+                Some(output) => crates.save(output)?,
+                None => print!("{crates}"),
+            }
+        }
+        Command::Rps {
+            rounds,
+            seed,
+            input,
+            decoding,
+            game,
+            stream,
+            report,
+            counter,
+        } => {
+            let input = match input {
+                Some(input) => input,
+                None => fetch::default_input(runner::find_day(RockPaperScissors::DAY)?)?,
+            };
+            if let Some(every) = stream {
+                let reader = BufReader::new(File::open(input)?);
+                let totals = rps::stream::score_lines(reader, &decoding, every, |totals| {
+                    eprintln!("{totals}")
+                })?;
+                println!("{}", totals.score);
+                return Ok(());
+            }
+            let strategy = Strategy::load(input, &decoding)?;
+            if let Some(format) = report {
+                print!("{}", format.render(&strategy.report()));
+                return Ok(());
+            }
+            if let Some(constraints) = counter {
+                let opponent: Vec<Move> = strategy.rounds().iter().map(|r| r.opponent).collect();
+                let best = counter::best_response(&opponent, &constraints)?;
+                eprintln!("score {}", best.score);
+                print!("{best}");
+                return Ok(());
+            }
+            let game = match game {
+                Some(game) => Game::load(game)?,
+                None => Game::rock_paper_scissors(),
+            };
+            let mut players: Vec<Box<dyn Player>> = vec![
+                Box::new(RandomPlayer::new(seed)),
+                Box::new(Cyclic { first: 0 }),
+                Box::new(FrequencyCounter),
+                Box::new(WinStayLoseShift),
+            ];
+            match Replay::mine(&strategy, &game) {
+                Some(replay) => players.push(Box::new(replay)),
+                None => eprintln!(
+                    "The game is missing moves from the strategy guide, so it won't be replayed"
+                ),
+            }
+            let tournament = engine::tournament(&game, &mut players, rounds)?;
+            for game in tournament.matches.iter() {
+                eprintln!("{game}");
+            }
+            print!("{tournament}");
+        }
+        Command::Help => println!("{USAGE}"),
+    }
     Ok(())
 }
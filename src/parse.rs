@@ -0,0 +1,131 @@
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+/// A 1-based line and column (counted in characters) in a puzzle input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// An error found while parsing a puzzle input. `K` is the format specific reason, which describes
+/// what was expected at `position`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError<K> {
+    pub position: Position,
+    /// The offending text, empty if the line ended early.
+    pub found: String,
+    pub kind: K,
+}
+
+impl<K: Display> Display for ParseError<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.found.is_empty() {
+            write!(f, "{}: {}, found end of line", self.position, self.kind)
+        } else {
+            write!(
+                f,
+                "{}: {}, found {:?}",
+                self.position, self.kind, self.found
+            )
+        }
+    }
+}
+
+impl<K: Debug + Display> std::error::Error for ParseError<K> {}
+
+/// A single line of input, which knows where it is so it can build positioned errors.
+#[derive(Debug, Clone, Copy)]
+pub struct Line<'a> {
+    /// 1-based line number.
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// The position of `token`, which must be a slice of this line's text.
+    pub fn position_of(&self, token: &str) -> Position {
+        let offset = (token.as_ptr() as usize)
+            .saturating_sub(self.text.as_ptr() as usize)
+            .min(self.text.len());
+        Position {
+            line: self.number,
+            column: self.text[..offset].chars().count() + 1,
+        }
+    }
+
+    /// The position just past the last character of the line.
+    pub fn end(&self) -> Position {
+        Position {
+            line: self.number,
+            column: self.text.chars().count() + 1,
+        }
+    }
+
+    pub fn error<K>(&self, token: &str, kind: K) -> ParseError<K> {
+        ParseError {
+            position: self.position_of(token),
+            found: token.to_owned(),
+            kind,
+        }
+    }
+
+    pub fn error_at_end<K>(&self, kind: K) -> ParseError<K> {
+        ParseError {
+            position: self.end(),
+            found: String::new(),
+            kind,
+        }
+    }
+
+    /// The next whitespace separated word, or an error at the end of the line.
+    pub fn expect_word<K>(
+        &self,
+        words: &mut impl Iterator<Item = &'a str>,
+        kind: K,
+    ) -> Result<&'a str, ParseError<K>> {
+        words.next().ok_or_else(|| self.error_at_end(kind))
+    }
+
+    /// Consume the next word, which must be exactly `keyword`.
+    pub fn expect_keyword<K>(
+        &self,
+        words: &mut impl Iterator<Item = &'a str>,
+        keyword: &str,
+        kind: K,
+    ) -> Result<(), ParseError<K>> {
+        match words.next() {
+            Some(word) if word == keyword => Ok(()),
+            Some(word) => Err(self.error(word, kind)),
+            None => Err(self.error_at_end(kind)),
+        }
+    }
+
+    /// Parse the next word as a `T`.
+    pub fn expect_parsed<T: FromStr, K>(
+        &self,
+        words: &mut impl Iterator<Item = &'a str>,
+        kind: K,
+    ) -> Result<T, ParseError<K>> {
+        match words.next() {
+            Some(word) => word.parse().map_err(|_| self.error(word, kind)),
+            None => Err(self.error_at_end(kind)),
+        }
+    }
+}
+
+/// Numbered lines of `data`.
+pub fn lines(data: &str) -> impl Iterator<Item = Line<'_>> {
+    data.lines().enumerate().map(|(i, text)| Line {
+        number: i + 1,
+        text,
+    })
+}
@@ -1,7 +1,19 @@
-use std::path::PathBuf;
+use std::{fmt::Display, fs::File, io::Read, path::PathBuf, str::FromStr};
 
 use anyhow::anyhow;
 
+use crate::{
+    parse::{self, Line, ParseError},
+    solution::Solution,
+};
+
+pub mod counter;
+pub mod engine;
+pub mod mixed;
+pub mod report;
+pub mod stream;
+pub mod variant;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Move {
     Rock,
@@ -10,6 +22,8 @@ pub enum Move {
 }
 
 impl Move {
+    pub const ALL: [Move; 3] = [Move::Rock, Move::Paper, Move::Scissors];
+
     pub fn point_value(&self) -> usize {
         match self {
             Move::Rock => 1,
@@ -18,6 +32,24 @@ impl Move {
         }
     }
 
+    /// The position of the move in [`Move::ALL`], for indexing anything kept per move.
+    pub fn index(self) -> usize {
+        match self {
+            Move::Rock => 0,
+            Move::Paper => 1,
+            Move::Scissors => 2,
+        }
+    }
+
+    /// The name of the move in lower case, as reports and game definitions spell it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Move::Rock => "rock",
+            Move::Paper => "paper",
+            Move::Scissors => "scissors",
+        }
+    }
+
     pub fn losing_response(self) -> Move {
         match self {
             Move::Rock => Move::Scissors,
@@ -68,9 +100,10 @@ impl Outcome {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Round {
-    opponent: Move,
-    me: Move,
+    pub opponent: Move,
+    pub me: Move,
 }
 
 impl Round {
@@ -79,74 +112,439 @@ impl Round {
     }
 }
 
+/// What a symbol in my column of a strategy guide tells me to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    /// Play this move.
+    Move(Move),
+    /// Play whatever move gets this outcome against the opponent.
+    Outcome(Outcome),
+}
+
+impl Response {
+    fn my_move(self, opponent: Move) -> Move {
+        match self {
+            Response::Move(me) => me,
+            Response::Outcome(outcome) => opponent.move_needed_for(outcome),
+        }
+    }
+}
+
+/// How to read the two columns of a strategy guide: which opponent move each symbol in the first
+/// column stands for, and which [`Response`] each symbol in the second.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Decoding {
+    opponent: Vec<(String, Move)>,
+    mine: Vec<(String, Response)>,
+}
+
+impl Decoding {
+    /// `A`, `B` and `C` are the opponent playing rock, paper and scissors, and `X`, `Y` and `Z` are
+    /// me playing rock, paper and scissors. This is how part 1 reads the guide.
+    pub fn literal_moves() -> Self {
+        Decoding::default()
+            .opponent_moves(["A", "B", "C"])
+            .my_move("X", Move::Rock)
+            .my_move("Y", Move::Paper)
+            .my_move("Z", Move::Scissors)
+    }
+
+    /// `A`, `B` and `C` are the opponent playing rock, paper and scissors, and `X`, `Y` and `Z` mean
+    /// I need to lose, tie or win. This is how part 2 reads the guide.
+    pub fn desired_outcomes() -> Self {
+        Decoding::default()
+            .opponent_moves(["A", "B", "C"])
+            .outcome("X", Outcome::Loss)
+            .outcome("Y", Outcome::Tie)
+            .outcome("Z", Outcome::Win)
+    }
+
+    /// The decoding `spec` describes: `literal` for [`Decoding::literal_moves`], `outcomes` for
+    /// [`Decoding::desired_outcomes`], or a comma-separated list of symbols and what they mean, like
+    /// `A=them:rock,B=them:paper,C=them:scissors,X=lose,Y=draw,Z=win`.
+    ///
+    /// Moves say which column they're for, `them:` for the opponent's and `me:` for mine. Outcomes
+    /// (`lose`, `draw` or `tie`, and `win`) are always for my column, and may say so with `me:` too.
+    /// The opponent's column needs a symbol for every move, my column needs at least one symbol, and
+    /// no symbol may be given twice for the same column.
+    pub fn parse(spec: &str) -> Result<Decoding, anyhow::Error> {
+        match spec {
+            "literal" => return Ok(Decoding::literal_moves()),
+            "outcomes" => return Ok(Decoding::desired_outcomes()),
+            _ => {}
+        }
+        let mut decoding = Decoding::default();
+        for entry in spec.split(',') {
+            let (symbol, meaning) = entry
+                .split_once('=')
+                .filter(|(symbol, _)| !symbol.is_empty() && !symbol.contains(char::is_whitespace))
+                .ok_or_else(|| anyhow!("Invalid decoding: {entry} is not <symbol>=<meaning>"))?;
+            let (column, name) = match meaning.split_once(':') {
+                Some((column, name)) => (Some(column), name),
+                None => (None, meaning),
+            };
+            let response = match name {
+                "rock" => Response::Move(Move::Rock),
+                "paper" => Response::Move(Move::Paper),
+                "scissors" => Response::Move(Move::Scissors),
+                "lose" | "loss" => Response::Outcome(Outcome::Loss),
+                "draw" | "tie" => Response::Outcome(Outcome::Tie),
+                "win" => Response::Outcome(Outcome::Win),
+                _ => return Err(anyhow!("Invalid decoding: unknown meaning {name}")),
+            };
+            let defined = match (column, response) {
+                (Some("them"), Response::Move(m)) => {
+                    let defined = decoding.decode_opponent(symbol).is_some();
+                    decoding = decoding.opponent(symbol, m);
+                    defined
+                }
+                (Some("me"), _) | (None, Response::Outcome(_)) => {
+                    let defined = decoding.decode_mine(symbol).is_some();
+                    decoding = decoding.response(symbol, response);
+                    defined
+                }
+                (None, Response::Move(_)) => {
+                    return Err(anyhow!(
+                        "Invalid decoding: {entry} needs them: or me: to say whose move it is"
+                    ))
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Invalid decoding: {entry} is not a meaning for either column"
+                    ))
+                }
+            };
+            if defined {
+                return Err(anyhow!(
+                    "Invalid decoding: {symbol} is given twice for the same column"
+                ));
+            }
+        }
+        if let Some(m) = Move::ALL
+            .into_iter()
+            .find(|&m| decoding.opponent.iter().all(|&(_, o)| o != m))
+        {
+            return Err(anyhow!(
+                "Invalid decoding: {spec} has no symbol for the opponent playing {}",
+                m.name()
+            ));
+        }
+        if decoding.mine.is_empty() {
+            return Err(anyhow!(
+                "Invalid decoding: {spec} has no symbols for my column"
+            ));
+        }
+        Ok(decoding)
+    }
+
+    /// Read `symbol` in the first column as the opponent playing `opponent`.
+    pub fn opponent(mut self, symbol: impl Into<String>, opponent: Move) -> Self {
+        let symbol = symbol.into();
+        self.opponent.retain(|(s, _)| *s != symbol);
+        self.opponent.push((symbol, opponent));
+        self
+    }
+
+    /// Read the symbols in the first column as the opponent playing rock, paper and scissors.
+    pub fn opponent_moves(self, symbols: [&str; 3]) -> Self {
+        symbols
+            .into_iter()
+            .zip(Move::ALL)
+            .fold(self, |decoding, (symbol, m)| decoding.opponent(symbol, m))
+    }
+
+    /// Read `symbol` in the second column as `response`.
+    pub fn response(mut self, symbol: impl Into<String>, response: Response) -> Self {
+        let symbol = symbol.into();
+        self.mine.retain(|(s, _)| *s != symbol);
+        self.mine.push((symbol, response));
+        self
+    }
+
+    /// Read `symbol` in the second column as me playing `me`.
+    pub fn my_move(self, symbol: impl Into<String>, me: Move) -> Self {
+        self.response(symbol, Response::Move(me))
+    }
+
+    /// Read `symbol` in the second column as me needing to get `outcome`.
+    pub fn outcome(self, symbol: impl Into<String>, outcome: Outcome) -> Self {
+        self.response(symbol, Response::Outcome(outcome))
+    }
+
+    fn decode_opponent(&self, symbol: &str) -> Option<Move> {
+        self.opponent
+            .iter()
+            .find(|(s, _)| s == symbol)
+            .map(|&(_, m)| m)
+    }
+
+    fn decode_mine(&self, symbol: &str) -> Option<Response> {
+        self.mine.iter().find(|(s, _)| s == symbol).map(|&(_, r)| r)
+    }
+
+    /// The round on one line of a strategy guide.
+    fn decode_line(&self, line: &Line) -> Result<Round, ParseError<StrategyError>> {
+        let mut symbols = line.text.split_whitespace();
+
+        let opponent = line
+            .expect_word(&mut symbols, StrategyError::OpponentMove)
+            .and_then(|symbol| {
+                self.decode_opponent(symbol)
+                    .ok_or_else(|| line.error(symbol, StrategyError::OpponentMove))
+            })?;
+
+        let me = line
+            .expect_word(&mut symbols, StrategyError::MyColumn)
+            .and_then(|symbol| {
+                self.decode_mine(symbol)
+                    .ok_or_else(|| line.error(symbol, StrategyError::MyColumn))
+            })?
+            .my_move(opponent);
+
+        Ok(Round { opponent, me })
+    }
+}
+
+#[derive(Debug)]
 pub struct Strategy {
     actions: Vec<Round>,
 }
 
 impl Strategy {
+    pub fn rounds(&self) -> &[Round] {
+        &self.actions
+    }
+
     pub fn run_and_score(&self) -> usize {
         self.actions.iter().map(|round| round.my_score()).sum()
     }
 
-    pub fn load(file: impl Into<PathBuf>) -> Result<Strategy, anyhow::Error> {
-        let data = std::fs::read_to_string(file.into())?;
-        let mut actions = Vec::new();
-        for line in data.lines() {
-            let mut moves = line.split_whitespace();
-
-            let opponent = moves
-                .next()
-                .ok_or_else(|| anyhow!("No opponent move"))
-                .and_then(|input| match input {
-                    "A" => Ok(Move::Rock),
-                    "B" => Ok(Move::Paper),
-                    "C" => Ok(Move::Scissors),
-                    _ => Err(anyhow!("Invalid opponent input: {input}")),
-                })?;
-
-            let me = moves
-                .next()
-                .ok_or_else(|| anyhow!("No self move"))
-                .and_then(|input| match input {
-                    "X" => Ok(opponent.move_needed_for(Outcome::Loss)),
-                    "Y" => Ok(opponent.move_needed_for(Outcome::Tie)),
-                    "Z" => Ok(opponent.move_needed_for(Outcome::Win)),
-                    _ => Err(anyhow!("Invalid self input: {input}")),
-                })?;
-
-            actions.push(Round { opponent, me });
-        }
+    /// Statistics about the rounds, beyond the total score.
+    pub fn report(&self) -> report::Report {
+        report::Report::new(&self.actions)
+    }
+
+    pub fn load(file: impl Into<PathBuf>, decoding: &Decoding) -> Result<Strategy, anyhow::Error> {
+        Self::from_reader(File::open(file.into())?, decoding)
+    }
+
+    pub fn from_reader(reader: impl Read, decoding: &Decoding) -> Result<Strategy, anyhow::Error> {
+        Ok(Self::parse(&std::io::read_to_string(reader)?, decoding)?)
+    }
+
+    pub fn parse(data: &str, decoding: &Decoding) -> Result<Strategy, ParseError<StrategyError>> {
+        let actions = parse::lines(data)
+            .map(|line| decoding.decode_line(&line))
+            .collect::<Result<_, _>>()?;
         Ok(Strategy { actions })
     }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrategyError {
+    OpponentMove,
+    MyColumn,
+}
 
-    pub fn load_incorrect(file: impl Into<PathBuf>) -> Result<Strategy, anyhow::Error> {
-        let data = std::fs::read_to_string(file.into())?;
-        let mut actions = Vec::new();
-        for line in data.lines() {
-            let mut moves = line.split_whitespace();
-
-            let opponent = moves
-                .next()
-                .ok_or_else(||anyhow!("No opponent move"))
-                .and_then(|input| match input {
-                    "A" => Ok(Move::Rock),
-                    "B" => Ok(Move::Paper),
-                    "C" => Ok(Move::Scissors),
-                    _ => Err(anyhow!("Invalid opponent input: {input}")),
-                })?;
-
-            let me = moves
-                .next()
-                .ok_or_else(||anyhow!("No self move"))
-                .and_then(|input| match input {
-                    "X" => Ok(Move::Rock),
-                    "Y" => Ok(Move::Paper),
-                    "Z" => Ok(Move::Scissors),
-                    _ => Err(anyhow!("Invalid self input: {input}")),
-                })?;
-
-            actions.push(Round { opponent, me });
+impl Display for StrategyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StrategyError::OpponentMove => write!(f, "expected a symbol for an opponent move"),
+            StrategyError::MyColumn => {
+                write!(f, "expected a symbol for my move or the outcome I need")
+            }
+        }
+    }
+}
+
+/// Reads the guide as [`Decoding::desired_outcomes`].
+impl FromStr for Strategy {
+    type Err = ParseError<StrategyError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        Strategy::parse(data, &Decoding::desired_outcomes())
+    }
+}
+
+pub struct RockPaperScissors;
+
+impl Solution for RockPaperScissors {
+    const DAY: u8 = 2;
+
+    /// The strategy guide decoded both ways: `XYZ` as my move, and `XYZ` as the desired outcome.
+    type Input = (Strategy, Strategy);
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error> {
+        Ok((
+            Strategy::parse(input, &Decoding::literal_moves())?,
+            Strategy::parse(input, &Decoding::desired_outcomes())?,
+        ))
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.0.run_and_score())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.1.run_and_score())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::{prop, prop_assert_eq, proptest};
+
+    use super::*;
+
+    pub(super) const EXAMPLE: &str = "A Y\nB X\nC Z\n";
+
+    #[test]
+    fn example() {
+        let input = RockPaperScissors::parse(EXAMPLE).unwrap();
+        assert_eq!(RockPaperScissors::part1(&input).unwrap(), 15);
+        assert_eq!(RockPaperScissors::part2(&input).unwrap(), 12);
+    }
+
+    #[test]
+    fn move_indices_and_names() {
+        for (i, m) in Move::ALL.into_iter().enumerate() {
+            assert_eq!(m.index(), i);
+            assert_eq!(m.point_value(), i + 1);
+            assert_eq!(m.name(), format!("{m:?}").to_lowercase());
+        }
+    }
+
+    #[test]
+    fn invalid_move() {
+        let error = "A Y\nB W\n".parse::<Strategy>().unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 2, column: 3 });
+        assert_eq!(error.kind, StrategyError::MyColumn);
+
+        let error = "A Y\nB\n".parse::<Strategy>().unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 2, column: 2 });
+        assert_eq!(error.found, "");
+    }
+
+    #[test]
+    fn custom_decoding() {
+        let decoding = Decoding::default()
+            .opponent_moves(["rock", "paper", "scissors"])
+            .my_move("P", Move::Paper)
+            .outcome("win", Outcome::Win)
+            .outcome("draw", Outcome::Tie);
+        let strategy = Strategy::parse("rock P\npaper win\nscissors draw\n", &decoding).unwrap();
+        let mine: Vec<Move> = strategy.rounds().iter().map(|round| round.me).collect();
+        assert_eq!(mine, [Move::Paper, Move::Scissors, Move::Scissors]);
+
+        // A later definition of a symbol replaces the earlier one.
+        let decoding = Decoding::literal_moves().outcome("X", Outcome::Win);
+        let strategy = Strategy::parse("A X\n", &decoding).unwrap();
+        assert_eq!(strategy.rounds()[0].me, Move::Paper);
+
+        let strategy = Strategy::parse("A Y\n", &decoding).unwrap();
+        assert_eq!(strategy.run_and_score(), 8);
+        let error = Strategy::parse("rock P\n", &Decoding::literal_moves()).unwrap_err();
+        assert_eq!(error.kind, StrategyError::OpponentMove);
+    }
+
+    #[test]
+    fn decoding_specs() {
+        let cases = [
+            ("literal", Decoding::literal_moves()),
+            ("outcomes", Decoding::desired_outcomes()),
+            (
+                "A=them:rock,B=them:paper,C=them:scissors,X=lose,Y=draw,Z=win",
+                Decoding::desired_outcomes(),
+            ),
+            (
+                "A=them:rock,X=me:rock,Y=me:paper,Z=me:scissors,B=them:paper,C=them:scissors",
+                Decoding::literal_moves(),
+            ),
+            (
+                "X=me:lose,A=them:rock,B=them:paper,C=them:scissors,Y=tie,Z=me:win",
+                Decoding::desired_outcomes(),
+            ),
+            // The same symbol can mean different things in each column.
+            (
+                "A=them:rock,B=them:paper,C=them:scissors,A=me:paper",
+                Decoding::default()
+                    .opponent_moves(["A", "B", "C"])
+                    .my_move("A", Move::Paper),
+            ),
+        ];
+        for (spec, expected) in cases {
+            assert_eq!(Decoding::parse(spec).unwrap(), expected, "{spec}");
+        }
+
+        let decoding =
+            Decoding::parse("r=them:rock,R=them:rock,p=them:paper,s=them:scissors,w=win").unwrap();
+        let strategy = Strategy::parse("R w\np w\n", &decoding).unwrap();
+        assert_eq!(strategy.rounds()[0].me, Move::Paper);
+        assert_eq!(strategy.rounds()[1].me, Move::Scissors);
+
+        let errors = [
+            ("", " is not <symbol>=<meaning>"),
+            ("=them:rock", "=them:rock is not <symbol>=<meaning>"),
+            (
+                "A=rock,B=them:paper,C=them:scissors,X=win",
+                "A=rock needs them: or me: to say whose move it is",
+            ),
+            (
+                "A=them:rock,X=rock,Y=paper,Z=scissors,B=them:paper,C=them:scissors",
+                "X=rock needs them: or me: to say whose move it is",
+            ),
+            (
+                "A=them:rock,B=them:paper,C=them:win",
+                "C=them:win is not a meaning for either column",
+            ),
+            ("A=us:rock", "A=us:rock is not a meaning for either column"),
+            ("A=them:spock", "unknown meaning spock"),
+            (
+                "A=them:rock,B=them:paper,X=me:rock",
+                "A=them:rock,B=them:paper,X=me:rock has no symbol for the opponent playing scissors",
+            ),
+            (
+                "A=them:rock,B=them:paper,C=them:scissors",
+                "A=them:rock,B=them:paper,C=them:scissors has no symbols for my column",
+            ),
+            (
+                "A=them:rock,A=them:paper,C=them:scissors,X=win",
+                "A is given twice for the same column",
+            ),
+            (
+                "A=them:rock,B=them:paper,C=them:scissors,X=win,X=me:rock",
+                "X is given twice for the same column",
+            ),
+        ];
+        for (spec, message) in errors {
+            let error = Decoding::parse(spec).unwrap_err().to_string();
+            assert_eq!(error, format!("Invalid decoding: {message}"), "{spec}");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn score_matches_modular_arithmetic(opponent in 0..3usize, me in 0..3usize) {
+            let round = Round { opponent: Move::ALL[opponent], me: Move::ALL[me] };
+            // Each move beats the one before it, so the outcome is the difference mod 3.
+            let outcome = (me + 4 - opponent) % 3;
+            prop_assert_eq!(round.my_score(), me + 1 + outcome * 3);
+        }
+
+        #[test]
+        fn outcome_column_is_respected(lines in prop::collection::vec((0..3usize, 0..3usize), 0..50)) {
+            let data: String = lines
+                .iter()
+                .map(|&(opponent, outcome)| {
+                    format!("{} {}\n", b"ABC"[opponent] as char, b"XYZ"[outcome] as char)
+                })
+                .collect();
+            let strategy: Strategy = data.parse().unwrap();
+            for (round, (_, outcome)) in strategy.actions.iter().zip(lines.iter()) {
+                let expected = [Outcome::Loss, Outcome::Tie, Outcome::Win][*outcome];
+                prop_assert_eq!(round.opponent.vs_my_move(round.me), expected);
+            }
         }
-        Ok(Strategy { actions })
     }
 }
@@ -0,0 +1,461 @@
+use std::{collections::HashMap, fmt::Display};
+
+use super::{Move, Outcome, Round};
+
+/// Number of states [`best_response`] keeps track of before giving up.
+pub const DEFAULT_BUDGET: usize = 10_000_000;
+
+/// Limits on how I may respond to the opponent.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Constraints {
+    /// Lose at least this many rounds.
+    pub min_losses: usize,
+    /// Play the same move at most this many times in a row.
+    pub max_run: Option<usize>,
+    /// Play exactly this many of each move, in the order of [`Move::ALL`].
+    pub counts: Option<[usize; 3]>,
+}
+
+impl Constraints {
+    pub fn with_min_losses(mut self, min_losses: usize) -> Self {
+        self.min_losses = min_losses;
+        self
+    }
+
+    pub fn with_max_run(mut self, max_run: usize) -> Self {
+        self.max_run = Some(max_run);
+        self
+    }
+
+    pub fn with_counts(mut self, counts: [usize; 3]) -> Self {
+        self.counts = Some(counts);
+        self
+    }
+}
+
+/// The best way to respond to a known sequence of opponent moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterStrategy {
+    pub rounds: Vec<Round>,
+    /// My total score over every round.
+    pub score: usize,
+}
+
+/// The rounds as a strategy guide, with the opponent's moves as A, B, C and mine as X, Y, Z, so it
+/// can be read back with [`super::Decoding::literal_moves`].
+impl Display for CounterStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for round in self.rounds.iter() {
+            let letter = |first: u8, m: Move| char::from(first + m.index() as u8);
+            writeln!(
+                f,
+                "{} {}",
+                letter(b'A', round.opponent),
+                letter(b'X', round.me)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterError {
+    /// No sequence of moves meets every constraint.
+    Infeasible,
+    /// The search kept track of `states` states without getting through every round.
+    BudgetExhausted { states: usize },
+}
+
+impl Display for CounterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CounterError::Infeasible => write!(f, "no sequence of moves meets every constraint"),
+            CounterError::BudgetExhausted { states } => write!(
+                f,
+                "gave up after keeping track of {states} states without getting through every round"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CounterError {}
+
+/// What matters about the rounds played so far for the rounds still to come. Anything a
+/// constraint doesn't need is left at zero, so those states merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    /// Index in [`Move::ALL`] of the last move, and how many times in a row it was played.
+    last: usize,
+    run: usize,
+    /// Rounds lost, up to the number that need to be lost.
+    losses: usize,
+    /// How many rocks and papers were played. The rest of the rounds were scissors.
+    used: [usize; 2],
+}
+
+/// How a state was best reached: the index of the state it came from in the round before, and the
+/// move played.
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    previous: u32,
+    me: Move,
+}
+
+/// Find the highest scoring responses to `opponent` that meet `constraints`, giving up after
+/// keeping track of [`DEFAULT_BUDGET`] states.
+///
+/// This is a dynamic program over the rounds, where `min_losses` and `max_run` multiply the work
+/// per round by their value. Exact `counts` would multiply it by the square of the number of rounds,
+/// so without `max_run`, when the order of the rounds doesn't matter, they're solved by counting
+/// instead. Together with `max_run`, they're only practical for short sequences, and longer ones
+/// run out of budget.
+pub fn best_response(
+    opponent: &[Move],
+    constraints: &Constraints,
+) -> Result<CounterStrategy, CounterError> {
+    best_response_within(opponent, constraints, DEFAULT_BUDGET)
+}
+
+/// Like [`best_response`], but giving up after keeping track of `budget` states.
+pub fn best_response_within(
+    opponent: &[Move],
+    constraints: &Constraints,
+    budget: usize,
+) -> Result<CounterStrategy, CounterError> {
+    if let Some(counts) = constraints.counts {
+        if counts.iter().sum::<usize>() != opponent.len() {
+            return Err(CounterError::Infeasible);
+        }
+        if constraints.max_run.is_none() {
+            return with_counts(opponent, counts, constraints.min_losses);
+        }
+    }
+
+    let start = State {
+        last: 0,
+        run: 0,
+        losses: 0,
+        used: [0; 2],
+    };
+    // The best score reaching each state after the rounds so far. Earlier rounds only keep how
+    // each of their states was reached.
+    let mut layer: Vec<(State, usize)> = vec![(start, 0)];
+    let mut steps: Vec<Vec<Step>> = Vec::with_capacity(opponent.len());
+    let mut states = layer.len();
+
+    for (played, &their_move) in opponent.iter().enumerate() {
+        let mut next: Vec<(State, usize)> = Vec::new();
+        let mut reached: Vec<Step> = Vec::new();
+        let mut index: HashMap<State, usize> = HashMap::new();
+
+        for (previous, &(state, score)) in layer.iter().enumerate() {
+            for (m, me) in Move::ALL.into_iter().enumerate() {
+                let Some(state) = advance(&state, played, m, their_move, constraints) else {
+                    continue;
+                };
+                let round = Round {
+                    opponent: their_move,
+                    me,
+                };
+                let score = score + round.my_score();
+                let step = Step {
+                    previous: previous as u32,
+                    me,
+                };
+                match index.get(&state) {
+                    Some(&i) if next[i].1 >= score => {}
+                    Some(&i) => {
+                        next[i].1 = score;
+                        reached[i] = step;
+                    }
+                    None => {
+                        index.insert(state, next.len());
+                        next.push((state, score));
+                        reached.push(step);
+                    }
+                }
+            }
+        }
+
+        states += next.len();
+        if states > budget {
+            return Err(CounterError::BudgetExhausted { states });
+        }
+        steps.push(reached);
+        layer = next;
+    }
+
+    // Exact counts were checked against the number of rounds, so only the losses can fall short.
+    let (mut position, &(_, score)) = layer
+        .iter()
+        .enumerate()
+        .filter(|(_, (state, _))| state.losses == constraints.min_losses)
+        .max_by_key(|(_, (_, score))| *score)
+        .ok_or(CounterError::Infeasible)?;
+
+    let mut rounds = Vec::with_capacity(opponent.len());
+    for (reached, &their_move) in steps.iter().zip(opponent).rev() {
+        let step = reached[position];
+        rounds.push(Round {
+            opponent: their_move,
+            me: step.me,
+        });
+        position = step.previous as usize;
+    }
+    rounds.reverse();
+    Ok(CounterStrategy { rounds, score })
+}
+
+/// Solve exact `counts` with no limit on runs, where only how often I win and lose against each of
+/// the opponent's moves matters.
+///
+/// Against opponent move `o`, say I win `w[o]` times, lose `l[o]` times and tie the rest. Playing
+/// `m` ties with `m`, beats the move before it and loses to the move after it, so the counts hold
+/// when, for every `m`, `counts[m] - a[m] = w[m - 1] + l[m + 1] - w[m] - l[m]`, where `a[m]` is how
+/// often the opponent plays `m`. Adding the same amount to every `w[o]`, or to every `l[o]`, keeps
+/// that true, so with `w = s + u` and `l = t + v` for `u[0] = v[0] = 0`, choosing `u[1]` and `u[2]`
+/// fixes `v`. That leaves `s` and `t`. My score is a constant plus three points for every win over
+/// a loss, so `t` is as small as the losses allow and `s` as large as the rounds allow.
+fn with_counts(
+    opponent: &[Move],
+    counts: [usize; 3],
+    min_losses: usize,
+) -> Result<CounterStrategy, CounterError> {
+    let mut played = [0i64; 3];
+    for &m in opponent {
+        played[m.index()] += 1;
+    }
+    let c: [i64; 3] = std::array::from_fn(|m| counts[m] as i64 - played[m]);
+    let min_losses = min_losses as i64;
+
+    // The most wins over losses, with the wins and losses against each opponent move.
+    let mut best: Option<(i64, [i64; 3], [i64; 3])> = None;
+    for u1 in -played[0]..=played[1] {
+        for u2 in -played[0]..=played[2] {
+            let u = [0, u1, u2];
+            let v = [0, c[0] - u2, c[0] + c[1] + u1 - u2];
+            let s_min = (0..3).map(|o| -u[o]).max().unwrap_or(0);
+            let most = (0..3).map(|o| played[o] - u[o] - v[o]).min().unwrap_or(0);
+            // The total losses are 3t + v[1] + v[2].
+            let losses_min = (min_losses - v[1] - v[2] + 2).div_euclid(3);
+            let t = (0..3).map(|o| -v[o]).max().unwrap_or(0).max(losses_min);
+            let s = most - t;
+            if s < s_min {
+                continue;
+            }
+            let margin = 3 * s + u1 + u2 - 3 * t - v[1] - v[2];
+            if best.is_none_or(|(best, _, _)| margin > best) {
+                best = Some((margin, u.map(|u| s + u), v.map(|v| t + v)));
+            }
+        }
+    }
+    let (_, mut wins, mut losses) = best.ok_or(CounterError::Infeasible)?;
+
+    let rounds: Vec<Round> = opponent
+        .iter()
+        .map(|&their_move| {
+            let o = their_move.index();
+            let me = if wins[o] > 0 {
+                wins[o] -= 1;
+                their_move.winning_response()
+            } else if losses[o] > 0 {
+                losses[o] -= 1;
+                their_move.losing_response()
+            } else {
+                their_move
+            };
+            Round {
+                opponent: their_move,
+                me,
+            }
+        })
+        .collect();
+    let score = rounds.iter().map(Round::my_score).sum();
+    Ok(CounterStrategy { rounds, score })
+}
+
+/// The state after playing `Move::ALL[m]` against `their_move`, with `played` rounds before it, or
+/// `None` if that breaks a constraint.
+fn advance(
+    state: &State,
+    played: usize,
+    m: usize,
+    their_move: Move,
+    constraints: &Constraints,
+) -> Option<State> {
+    let mut next = *state;
+    if let Some(max_run) = constraints.max_run {
+        let run = if state.run > 0 && state.last == m {
+            state.run + 1
+        } else {
+            1
+        };
+        if run > max_run {
+            return None;
+        }
+        next.last = m;
+        next.run = run;
+    }
+    if let Some(counts) = constraints.counts {
+        if let Some(used) = next.used.get_mut(m) {
+            *used += 1;
+        }
+        let [rocks, papers] = next.used;
+        let scissors = played + 1 - rocks - papers;
+        if rocks > counts[0] || papers > counts[1] || scissors > counts[2] {
+            return None;
+        }
+    }
+    if their_move.vs_my_move(Move::ALL[m]) == Outcome::Loss {
+        next.losses = (state.losses + 1).min(constraints.min_losses);
+    }
+    Some(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::{prop, prop_assert, prop_assert_eq, proptest};
+
+    use super::*;
+    use crate::rps::{tests::EXAMPLE, Decoding, Strategy};
+
+    /// Every response to `opponent` that meets `constraints`, scored.
+    fn brute_force(opponent: &[Move], constraints: &Constraints) -> Option<usize> {
+        let n = opponent.len();
+        (0..3usize.pow(n as u32))
+            .filter_map(|mut code| {
+                let mine: Vec<usize> = (0..n)
+                    .map(|_| {
+                        let m = code % 3;
+                        code /= 3;
+                        m
+                    })
+                    .collect();
+                let rounds: Vec<Round> = opponent
+                    .iter()
+                    .zip(mine.iter())
+                    .map(|(&opponent, &m)| Round {
+                        opponent,
+                        me: Move::ALL[m],
+                    })
+                    .collect();
+                meets(&rounds, constraints).then(|| rounds.iter().map(Round::my_score).sum())
+            })
+            .max()
+    }
+
+    fn meets(rounds: &[Round], constraints: &Constraints) -> bool {
+        let losses = rounds
+            .iter()
+            .filter(|r| r.opponent.vs_my_move(r.me) == Outcome::Loss)
+            .count();
+        let longest_run = rounds
+            .chunk_by(|a, b| a.me == b.me)
+            .map(<[Round]>::len)
+            .max()
+            .unwrap_or(0);
+        let counts = Move::ALL.map(|m| rounds.iter().filter(|r| r.me == m).count());
+        losses >= constraints.min_losses
+            && constraints.max_run.is_none_or(|max| longest_run <= max)
+            && constraints.counts.is_none_or(|c| c == counts)
+    }
+
+    #[test]
+    fn example() {
+        let strategy: Strategy = EXAMPLE.parse().unwrap();
+        let opponent: Vec<Move> = strategy.rounds().iter().map(|r| r.opponent).collect();
+
+        // Without constraints, win every round: paper, scissors, rock.
+        let best = best_response(&opponent, &Constraints::default()).unwrap();
+        assert_eq!(best.score, 2 + 3 + 1 + 3 * 6);
+        assert_eq!(best.to_string(), "A Y\nB Z\nC X\n");
+        let guide = Strategy::parse(&best.to_string(), &Decoding::literal_moves()).unwrap();
+        assert_eq!(guide.rounds(), best.rounds);
+
+        let constraints = Constraints::default().with_min_losses(1).with_max_run(1);
+        let best = best_response(&opponent, &constraints).unwrap();
+        assert!(meets(&best.rounds, &constraints));
+        assert_eq!(best.score, best.rounds.iter().map(Round::my_score).sum());
+
+        let constraints = Constraints::default().with_counts([3, 0, 0]);
+        let best = best_response(&opponent, &constraints).unwrap();
+        assert!(best.rounds.iter().all(|r| r.me == Move::Rock));
+
+        let impossible = Constraints::default().with_min_losses(4);
+        assert_eq!(
+            best_response(&opponent, &impossible),
+            Err(CounterError::Infeasible)
+        );
+        let impossible = Constraints::default().with_counts([1, 1, 0]);
+        assert_eq!(
+            best_response(&opponent, &impossible),
+            Err(CounterError::Infeasible)
+        );
+    }
+
+    #[test]
+    fn budget() {
+        // Exact counts with a limit on runs need a state for every way of splitting the rounds so
+        // far between the moves, which soon adds up.
+        let opponent: Vec<Move> = (0..2500).map(|i| Move::ALL[i % 3]).collect();
+        let constraints = Constraints::default()
+            .with_max_run(3)
+            .with_counts([800, 800, 900]);
+        let error = best_response_within(&opponent, &constraints, 100_000).unwrap_err();
+        assert!(matches!(error, CounterError::BudgetExhausted { states } if states > 100_000));
+
+        let constraints = Constraints::default().with_max_run(2).with_min_losses(2);
+        let best = best_response_within(&opponent[..30], &constraints, 30 * 3 * 2 * 3 + 1).unwrap();
+        assert!(meets(&best.rounds, &constraints));
+        let error = best_response_within(&opponent[..30], &constraints, 50);
+        assert!(matches!(error, Err(CounterError::BudgetExhausted { .. })));
+    }
+
+    proptest! {
+        #[test]
+        fn counting_matches_dynamic_programming(
+            opponent in prop::collection::vec(0..3usize, 0..30),
+            min_losses in 0..10usize,
+            (rock, paper) in (0..30usize, 0..30usize),
+        ) {
+            let opponent: Vec<Move> = opponent.into_iter().map(|m| Move::ALL[m]).collect();
+            let rock = rock.min(opponent.len());
+            let paper = paper.min(opponent.len() - rock);
+            let counts = [rock, paper, opponent.len() - rock - paper];
+            // A run can't be longer than every round, so this goes through the dynamic program.
+            let constraints = Constraints::default()
+                .with_min_losses(min_losses)
+                .with_counts(counts)
+                .with_max_run(opponent.len().max(1));
+            let expected = best_response(&opponent, &constraints).ok().map(|best| best.score);
+            let counted = with_counts(&opponent, counts, min_losses).ok();
+            prop_assert_eq!(counted.as_ref().map(|best| best.score), expected);
+            if let Some(best) = counted {
+                prop_assert!(meets(&best.rounds, &constraints));
+            }
+        }
+
+        #[test]
+        fn matches_brute_force(
+            opponent in prop::collection::vec(0..3usize, 0..7),
+            min_losses in 0..4usize,
+            max_run in prop::option::of(1..4usize),
+            counts in prop::option::of([0..4usize, 0..4usize]),
+        ) {
+            let opponent: Vec<Move> = opponent.into_iter().map(|m| Move::ALL[m]).collect();
+            let counts = counts.and_then(|[rock, paper]| {
+                let scissors = opponent.len().checked_sub(rock + paper)?;
+                Some([rock, paper, scissors])
+            });
+            let constraints = Constraints { min_losses, max_run, counts };
+
+            let expected = brute_force(&opponent, &constraints);
+            let best = best_response(&opponent, &constraints).ok();
+            prop_assert_eq!(best.as_ref().map(|best| best.score), expected);
+            if let Some(best) = best {
+                prop_assert!(meets(&best.rounds, &constraints));
+                prop_assert_eq!(best.score, best.rounds.iter().map(Round::my_score).sum::<usize>());
+            }
+        }
+    }
+}
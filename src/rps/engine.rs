@@ -0,0 +1,493 @@
+use std::fmt::Display;
+
+use super::{
+    variant::{Game, GameRound},
+    Move, Outcome, Round, Strategy,
+};
+use crate::rng::SplitMix64;
+
+/// Someone playing a [`Game`], who chooses each move knowing how the match has gone. Moves are
+/// indices into [`Game::moves`].
+pub trait Player {
+    fn name(&self) -> String;
+
+    /// Choose the next move, given the rounds played so far in this match. In each round, `me` is
+    /// this player's move.
+    fn choose(&mut self, game: &Game, history: &[GameRound]) -> usize;
+}
+
+/// Plays a seeded random move each round.
+#[derive(Debug, Clone)]
+pub struct RandomPlayer {
+    rng: SplitMix64,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        RandomPlayer {
+            rng: SplitMix64(seed),
+        }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn name(&self) -> String {
+        "random".into()
+    }
+
+    fn choose(&mut self, game: &Game, _history: &[GameRound]) -> usize {
+        self.rng.below(game.moves().len())
+    }
+}
+
+/// Starts with `first`, then plays whatever beats its previous move: rock, paper, scissors, rock...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cyclic {
+    pub first: usize,
+}
+
+impl Player for Cyclic {
+    fn name(&self) -> String {
+        "cyclic".into()
+    }
+
+    fn choose(&mut self, game: &Game, history: &[GameRound]) -> usize {
+        match history.last() {
+            None => self.first,
+            Some(last) => game.winning_response(last.me),
+        }
+    }
+}
+
+/// Plays whatever beats the opponent's most common move so far, starting with the first move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrequencyCounter;
+
+impl Player for FrequencyCounter {
+    fn name(&self) -> String {
+        "frequency".into()
+    }
+
+    fn choose(&mut self, game: &Game, history: &[GameRound]) -> usize {
+        let mut counts = vec![0; game.moves().len()];
+        for round in history {
+            counts[round.opponent] += 1;
+        }
+        // Ties go to the move that comes first.
+        let (favourite, _) = counts
+            .into_iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, count)| count)
+            .expect("a game has at least one move");
+        match history {
+            [] => 0,
+            _ => game.winning_response(favourite),
+        }
+    }
+}
+
+/// Starts with the first move, keeps playing a move while it wins, and otherwise switches to
+/// whatever would have beaten the opponent's last move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WinStayLoseShift;
+
+impl Player for WinStayLoseShift {
+    fn name(&self) -> String {
+        "win-stay-lose-shift".into()
+    }
+
+    fn choose(&mut self, game: &Game, history: &[GameRound]) -> usize {
+        match history.last() {
+            None => 0,
+            Some(last) if game.outcome(last.opponent, last.me) == Outcome::Win => last.me,
+            Some(last) => game.winning_response(last.opponent),
+        }
+    }
+}
+
+/// Plays a fixed list of moves, such as one side of a strategy guide, starting over when it runs
+/// out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub moves: Vec<usize>,
+}
+
+impl Replay {
+    /// Replay my moves from `strategy` in `game`, or `None` if the game has no move with the name
+    /// of one of them.
+    pub fn mine(strategy: &Strategy, game: &Game) -> Option<Self> {
+        Replay::side(strategy, game, |round| round.me)
+    }
+
+    /// Replay the opponent's moves from `strategy` in `game`, or `None` if the game has no move
+    /// with the name of one of them.
+    pub fn opponents(strategy: &Strategy, game: &Game) -> Option<Self> {
+        Replay::side(strategy, game, |round| round.opponent)
+    }
+
+    fn side(strategy: &Strategy, game: &Game, side: impl Fn(&Round) -> Move) -> Option<Self> {
+        let moves = strategy
+            .rounds()
+            .iter()
+            .map(|round| game.find(side(round).name()))
+            .collect::<Option<_>>()?;
+        Some(Replay { moves })
+    }
+}
+
+impl Player for Replay {
+    fn name(&self) -> String {
+        "replay".into()
+    }
+
+    /// Plays the first move if there are no moves to replay.
+    fn choose(&mut self, _game: &Game, history: &[GameRound]) -> usize {
+        match self.moves.len() {
+            0 => 0,
+            len => self.moves[history.len() % len],
+        }
+    }
+}
+
+/// The rounds of a match between two players.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub first: String,
+    pub second: String,
+    /// Every round, from the first player's side.
+    pub rounds: Vec<GameRound>,
+    /// The total score of each player, in the same order as the players.
+    pub scores: (usize, usize),
+}
+
+impl Match {
+    /// The result for the first player, by comparing their scores.
+    pub fn outcome(&self) -> Outcome {
+        let (first, second) = self.scores;
+        match first.cmp(&second) {
+            std::cmp::Ordering::Less => Outcome::Loss,
+            std::cmp::Ordering::Equal => Outcome::Tie,
+            std::cmp::Ordering::Greater => Outcome::Win,
+        }
+    }
+}
+
+impl Display for Match {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (first, second) = self.scores;
+        write!(
+            f,
+            "{} {first} - {second} {} ({} rounds)",
+            self.first,
+            self.second,
+            self.rounds.len()
+        )
+    }
+}
+
+/// A move a player chose that isn't one of the game's moves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidChoice {
+    pub player: String,
+    /// Index of the round, counting from 0.
+    pub round: usize,
+    pub choice: usize,
+    /// How many moves the game has.
+    pub moves: usize,
+}
+
+impl Display for InvalidChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} chose move {} in round {}, but the game only has {} moves",
+            self.player, self.choice, self.round, self.moves
+        )
+    }
+}
+
+impl std::error::Error for InvalidChoice {}
+
+/// Ask `player` for its next move, checking that it's one of the game's moves.
+fn choose(
+    game: &Game,
+    player: &mut dyn Player,
+    history: &[GameRound],
+) -> Result<usize, InvalidChoice> {
+    let choice = player.choose(game, history);
+    if choice >= game.moves().len() {
+        return Err(InvalidChoice {
+            player: player.name(),
+            round: history.len(),
+            choice,
+            moves: game.moves().len(),
+        });
+    }
+    Ok(choice)
+}
+
+/// Play `rounds` rounds of `game` between two players. Fails if either player chooses a move the
+/// game doesn't have.
+pub fn play(
+    game: &Game,
+    first: &mut dyn Player,
+    second: &mut dyn Player,
+    rounds: usize,
+) -> Result<Match, InvalidChoice> {
+    let mut history = Vec::with_capacity(rounds);
+    let mut their_history = Vec::with_capacity(rounds);
+    let mut scores = (0, 0);
+    for _ in 0..rounds {
+        let round = GameRound {
+            me: choose(game, first, &history)?,
+            opponent: choose(game, second, &their_history)?,
+        };
+        scores.0 += game.score(round.opponent, round.me);
+        scores.1 += game.score(round.me, round.opponent);
+        history.push(round);
+        their_history.push(round.flipped());
+    }
+    Ok(Match {
+        first: first.name(),
+        second: second.name(),
+        rounds: history,
+        scores,
+    })
+}
+
+/// How one player did in a tournament.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub name: String,
+    /// Total score over every round of every match.
+    pub score: usize,
+    pub wins: usize,
+    pub ties: usize,
+    pub losses: usize,
+}
+
+/// The results of every player meeting every other player once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tournament {
+    pub matches: Vec<Match>,
+    /// From the highest total score to the lowest.
+    pub standings: Vec<Standing>,
+}
+
+/// A line per player, from first place down, with their total score and wins-ties-losses.
+impl Display for Tournament {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .standings
+            .iter()
+            .map(|s| s.name.len())
+            .max()
+            .unwrap_or(0);
+        for (place, standing) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>2}. {:<width$}  {:>8}  {}-{}-{}",
+                place + 1,
+                standing.name,
+                standing.score,
+                standing.wins,
+                standing.ties,
+                standing.losses
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Play a `rounds`-round match of `game` between every pair of players. Fails if any player
+/// chooses a move the game doesn't have.
+pub fn tournament(
+    game: &Game,
+    players: &mut [Box<dyn Player>],
+    rounds: usize,
+) -> Result<Tournament, InvalidChoice> {
+    let mut standings: Vec<Standing> = players
+        .iter()
+        .map(|player| Standing {
+            name: player.name(),
+            score: 0,
+            wins: 0,
+            ties: 0,
+            losses: 0,
+        })
+        .collect();
+    let mut matches = Vec::new();
+
+    for i in 0..players.len() {
+        let (left, right) = players.split_at_mut(i + 1);
+        for (j, second) in right.iter_mut().enumerate() {
+            let j = i + 1 + j;
+            let played = play(game, left[i].as_mut(), second.as_mut(), rounds)?;
+            let (first_score, second_score) = played.scores;
+            standings[i].score += first_score;
+            standings[j].score += second_score;
+            match played.outcome() {
+                Outcome::Win => {
+                    standings[i].wins += 1;
+                    standings[j].losses += 1;
+                }
+                Outcome::Tie => {
+                    standings[i].ties += 1;
+                    standings[j].ties += 1;
+                }
+                Outcome::Loss => {
+                    standings[i].losses += 1;
+                    standings[j].wins += 1;
+                }
+            }
+            matches.push(played);
+        }
+    }
+
+    standings.sort_by_key(|standing| std::cmp::Reverse(standing.score));
+    Ok(Tournament { matches, standings })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::{prop_assert_eq, proptest};
+
+    use super::*;
+    use crate::rps::{tests::EXAMPLE, variant::GameMove, Decoding};
+
+    #[test]
+    fn replaying_a_strategy_scores_it() {
+        let strategy = Strategy::parse(EXAMPLE, &Decoding::literal_moves()).unwrap();
+        let game = Game::rock_paper_scissors();
+        let played = play(
+            &game,
+            &mut Replay::mine(&strategy, &game).unwrap(),
+            &mut Replay::opponents(&strategy, &game).unwrap(),
+            3,
+        )
+        .unwrap();
+        assert_eq!(played.scores.0, strategy.run_and_score());
+        assert_eq!(played.to_string(), "replay 15 - 15 replay (3 rounds)");
+
+        // Moves are matched up by name, and a game without them can't replay the guide.
+        let rpsls = Game::rock_paper_scissors_lizard_spock();
+        assert!(Replay::mine(&strategy, &rpsls).is_some());
+        let moves = (0..3).map(|i| GameMove::new(i.to_string(), 1)).collect();
+        let numbers = Game::cyclic(moves).unwrap();
+        assert_eq!(Replay::mine(&strategy, &numbers), None);
+    }
+
+    #[test]
+    fn built_in_players() {
+        let game = Game::rock_paper_scissors();
+        let [rock, paper, scissors] = Move::ALL.map(Move::index);
+        let mut cyclic = Cyclic { first: paper };
+        let played = play(&game, &mut cyclic, &mut WinStayLoseShift, 4).unwrap();
+        let moves: Vec<(usize, usize)> = played.rounds.iter().map(|r| (r.me, r.opponent)).collect();
+        // After losing the first round, win-stay-lose-shift always plays what beats cyclic's last
+        // move, which is just what cyclic plays next.
+        assert_eq!(
+            moves,
+            [
+                (paper, rock),
+                (scissors, scissors),
+                (rock, rock),
+                (paper, paper)
+            ]
+        );
+
+        // Frequency counting picks up on a player who always plays the same move.
+        let mut rocks = Replay { moves: vec![rock] };
+        let played = play(&game, &mut FrequencyCounter, &mut rocks, 10).unwrap();
+        assert!(played.rounds[1..].iter().all(|r| r.me == paper));
+        assert_eq!(played.outcome(), Outcome::Win);
+    }
+
+    #[test]
+    fn moves_outside_the_game() {
+        let game = Game::rock_paper_scissors();
+        let error = play(&game, &mut Cyclic { first: 5 }, &mut WinStayLoseShift, 3).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "cyclic chose move 5 in round 0, but the game only has 3 moves"
+        );
+
+        // The replay is fine until it runs into a move the game doesn't have.
+        let mut replay = Replay {
+            moves: vec![0, 1, 3],
+        };
+        let error = play(&game, &mut FrequencyCounter, &mut replay, 3).unwrap_err();
+        assert_eq!(error.player, "replay");
+        assert_eq!(error.round, 2);
+        assert!(play(&game, &mut FrequencyCounter, &mut replay, 2).is_ok());
+
+        let mut players: Vec<Box<dyn Player>> =
+            vec![Box::new(WinStayLoseShift), Box::new(Cyclic { first: 3 })];
+        let expected = InvalidChoice {
+            player: "cyclic".into(),
+            round: 0,
+            choice: 3,
+            moves: 3,
+        };
+        assert_eq!(tournament(&game, &mut players, 1), Err(expected));
+    }
+
+    #[test]
+    fn round_robin() {
+        for game in [
+            Game::rock_paper_scissors(),
+            Game::rock_paper_scissors_lizard_spock(),
+        ] {
+            let mut players: Vec<Box<dyn Player>> = vec![
+                Box::new(RandomPlayer::new(1)),
+                Box::new(Cyclic { first: 0 }),
+                Box::new(FrequencyCounter),
+                Box::new(WinStayLoseShift),
+                Box::new(Replay { moves: vec![2] }),
+            ];
+            let tournament = tournament(&game, &mut players, 100).unwrap();
+            assert_eq!(tournament.matches.len(), 10);
+            for standing in tournament.standings.iter() {
+                assert_eq!(standing.wins + standing.ties + standing.losses, 4);
+            }
+            assert!(tournament
+                .standings
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score));
+            let total: usize = tournament
+                .matches
+                .iter()
+                .map(|m| m.scores.0 + m.scores.1)
+                .sum();
+            let standings: usize = tournament.standings.iter().map(|s| s.score).sum();
+            assert_eq!(total, standings);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn each_round_shares_six_outcome_points(
+            seeds: (u64, u64),
+            rounds in 0..50usize,
+            half in 0..5usize,
+        ) {
+            let moves = (0..2 * half + 1).map(|i| GameMove::new(i.to_string(), i + 1)).collect();
+            let game = Game::cyclic(moves).unwrap();
+            let played = play(
+                &game,
+                &mut RandomPlayer::new(seeds.0),
+                &mut RandomPlayer::new(seeds.1),
+                rounds,
+            )
+            .unwrap();
+            let (first, second) = played.scores;
+            let points: usize = played
+                .rounds
+                .iter()
+                .map(|r| game.moves()[r.me].points + game.moves()[r.opponent].points)
+                .sum();
+            prop_assert_eq!(first + second, points + 6 * rounds);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt::Display;
+
+use super::{Move, Round};
+
+/// How far probabilities may be from adding up to one, or below zero, because of rounding.
+const TOLERANCE: f64 = 1e-9;
+
+/// A mixed strategy: each round, play each move with a fixed probability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mixed {
+    /// In the order of [`Move::ALL`].
+    probabilities: [f64; 3],
+}
+
+impl Mixed {
+    /// Play rock, paper and scissors with these probabilities, which must add up to one.
+    pub fn new(probabilities: [f64; 3]) -> Result<Mixed, MixedError> {
+        if probabilities.iter().any(|&p| p.is_nan() || p < -TOLERANCE) {
+            return Err(MixedError::Negative);
+        }
+        if (probabilities.iter().sum::<f64>() - 1.0).abs() > TOLERANCE {
+            return Err(MixedError::Sum);
+        }
+        Ok(Mixed {
+            probabilities: probabilities.map(|p| p.max(0.0)),
+        })
+    }
+
+    /// Always play `m`.
+    pub fn pure(m: Move) -> Mixed {
+        Mixed {
+            probabilities: Move::ALL.map(|other| f64::from(u8::from(other == m))),
+        }
+    }
+
+    /// Play every move equally often.
+    pub fn uniform() -> Mixed {
+        Mixed {
+            probabilities: [1.0 / 3.0; 3],
+        }
+    }
+
+    pub fn probability(&self, m: Move) -> f64 {
+        self.probabilities[m.index()]
+    }
+
+    /// Every move with its probability, in the order of [`Move::ALL`].
+    pub fn probabilities(&self) -> impl Iterator<Item = (Move, f64)> + '_ {
+        Move::ALL.into_iter().zip(self.probabilities)
+    }
+
+    /// My expected score in a round where I play this strategy and the opponent plays `opponent`.
+    pub fn expected_score(&self, opponent: &Mixed) -> f64 {
+        self.outcomes(opponent).map(|(p, score)| p * score).sum()
+    }
+
+    /// The variance of my score in a single round, like [`Mixed::expected_score`].
+    pub fn variance(&self, opponent: &Mixed) -> f64 {
+        let mean = self.expected_score(opponent);
+        self.outcomes(opponent)
+            .map(|(p, score)| p * (score - mean).powi(2))
+            .sum()
+    }
+
+    /// Every pair of moves, with how likely it is and my score for it.
+    fn outcomes<'a>(&'a self, opponent: &'a Mixed) -> impl Iterator<Item = (f64, f64)> + 'a {
+        self.probabilities().flat_map(move |(me, p)| {
+            opponent.probabilities().map(move |(their_move, q)| {
+                let round = Round {
+                    opponent: their_move,
+                    me,
+                };
+                (p * q, round.my_score() as f64)
+            })
+        })
+    }
+}
+
+/// The probabilities, as percentages.
+impl Display for Mixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let moves: Vec<_> = self
+            .probabilities()
+            .map(|(m, p)| format!("{} {:.1}%", m.name(), 100.0 * p))
+            .collect();
+        write!(f, "{}", moves.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixedError {
+    Negative,
+    Sum,
+}
+
+impl Display for MixedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MixedError::Negative => write!(f, "expected every probability to be at least zero"),
+            MixedError::Sum => write!(f, "expected the probabilities to add up to one"),
+        }
+    }
+}
+
+impl std::error::Error for MixedError {}
+
+/// A strategy that is the best response to itself, so neither player can do better by changing
+/// strategy alone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Equilibrium {
+    pub strategy: Mixed,
+    /// Each player's expected score per round.
+    pub expected_score: f64,
+}
+
+/// The symmetric Nash equilibrium of rock paper scissors when each player scores their own move's
+/// points on top of the outcome.
+///
+/// Scissors is worth the most points, so the equilibrium isn't uniform: rock, which beats scissors,
+/// is played more. The game isn't zero-sum, since the points for the moves don't cancel out, so
+/// this finds a strategy that makes every move it plays equally good against itself, and no other
+/// move better, by trying every set of moves to play.
+pub fn equilibrium() -> Equilibrium {
+    let score = |me: usize, opponent: usize| {
+        Round {
+            opponent: Move::ALL[opponent],
+            me: Move::ALL[me],
+        }
+        .my_score() as f64
+    };
+
+    // Try playing all three moves first, then pairs, then single moves.
+    let mut supports: Vec<Vec<usize>> = (1..8u8)
+        .map(|mask| (0..3).filter(|&m| mask & (1 << m) != 0).collect())
+        .collect();
+    supports.sort_by_key(|support: &Vec<usize>| std::cmp::Reverse(support.len()));
+
+    for support in supports {
+        // Every move in the support scores the same value `v` against the strategy, and the
+        // probabilities add up to one. The unknowns are the probabilities, then `v`.
+        let n = support.len();
+        let mut rows: Vec<Vec<f64>> = support
+            .iter()
+            .map(|&me| {
+                let mut row: Vec<f64> = support.iter().map(|&them| score(me, them)).collect();
+                row.extend([-1.0, 0.0]);
+                row
+            })
+            .collect();
+        let mut sum = vec![1.0; n];
+        sum.extend([0.0, 1.0]);
+        rows.push(sum);
+
+        let Some(solution) = solve(rows) else {
+            continue;
+        };
+        let mut probabilities = [0.0; 3];
+        for (&m, &p) in support.iter().zip(&solution) {
+            probabilities[m] = p;
+        }
+        let Ok(strategy) = Mixed::new(probabilities) else {
+            continue;
+        };
+        let value = solution[n];
+        let better = (0..3).any(|me| {
+            let against: f64 = (0..3)
+                .map(|them| probabilities[them] * score(me, them))
+                .sum();
+            against > value + TOLERANCE
+        });
+        if !better {
+            return Equilibrium {
+                strategy,
+                expected_score: value,
+            };
+        }
+    }
+    unreachable!("every finite game has a symmetric equilibrium")
+}
+
+/// Solve the square linear system whose augmented matrix is `rows`, by Gaussian elimination, or
+/// `None` if it has no single solution.
+fn solve(mut rows: Vec<Vec<f64>>) -> Option<Vec<f64>> {
+    let n = rows.len();
+    for column in 0..n {
+        let pivot =
+            (column..n).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+        if rows[pivot][column].abs() < TOLERANCE {
+            return None;
+        }
+        rows.swap(column, pivot);
+        let pivot = rows[column].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i != column {
+                let factor = row[column] / pivot[column];
+                for (x, p) in row.iter_mut().zip(&pivot) {
+                    *x -= factor * p;
+                }
+            }
+        }
+    }
+    Some((0..n).map(|row| rows[row][n] / rows[row][row]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::{prop_assert, proptest};
+
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn expected_scores() {
+        let paper = Mixed::pure(Move::Paper);
+        let rock = Mixed::pure(Move::Rock);
+        assert_eq!(paper.expected_score(&rock), 8.0);
+        assert_eq!(paper.variance(&rock), 0.0);
+
+        // Two points for the average move, and three for the average outcome.
+        assert!(close(Mixed::uniform().expected_score(&rock), 5.0));
+        // Rock against uniform scores 1 + 3, 1 + 0 or 1 + 6.
+        assert!(close(rock.variance(&Mixed::uniform()), 6.0));
+
+        assert_eq!(Mixed::new([0.5, 0.6, 0.0]), Err(MixedError::Sum));
+        assert_eq!(Mixed::new([1.5, -0.5, 0.0]), Err(MixedError::Negative));
+    }
+
+    #[test]
+    fn nash_equilibrium() {
+        let equilibrium = equilibrium();
+        let expected = [4.0 / 9.0, 1.0 / 9.0, 4.0 / 9.0];
+        for ((_, p), expected) in equilibrium.strategy.probabilities().zip(expected) {
+            assert!(close(p, expected));
+        }
+        assert!(close(equilibrium.expected_score, 5.0));
+        let strategy = equilibrium.strategy;
+        assert!(close(strategy.expected_score(&strategy), 5.0));
+        assert_eq!(
+            strategy.to_string(),
+            "rock 44.4%, paper 11.1%, scissors 44.4%"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn nothing_beats_the_equilibrium(weights in [0.0..1.0f64, 0.0..1.0f64, 0.0..1.0f64]) {
+            let total: f64 = weights.iter().sum();
+            let me = match Mixed::new(weights.map(|w| w / total)) {
+                Ok(me) => me,
+                Err(_) => Mixed::uniform(),
+            };
+            let equilibrium = equilibrium();
+            let score = me.expected_score(&equilibrium.strategy);
+            prop_assert!(score <= equilibrium.expected_score + 1e-6);
+            prop_assert!(me.variance(&equilibrium.strategy) >= 0.0);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+
+use super::{Move, Outcome, Round};
+
+/// One round of a [`Report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundReport {
+    pub round: Round,
+    pub outcome: Outcome,
+    /// Points for the move I played.
+    pub shape_points: usize,
+    /// Points for the outcome.
+    pub outcome_points: usize,
+    /// My score over this round and every round before it.
+    pub running_score: usize,
+}
+
+/// Statistics about a sequence of rounds, from my side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub rounds: Vec<RoundReport>,
+    pub wins: usize,
+    pub ties: usize,
+    pub losses: usize,
+    /// Points for the moves I played, over every round.
+    pub shape_points: usize,
+    /// Points for the outcomes, over every round.
+    pub outcome_points: usize,
+    /// The most rounds won in a row.
+    pub longest_win_streak: usize,
+    /// How often I played each move, in the order of [`Move::ALL`].
+    pub my_moves: [usize; 3],
+    /// How often the opponent played each move, in the order of [`Move::ALL`].
+    pub opponent_moves: [usize; 3],
+}
+
+impl Report {
+    pub fn new(rounds: &[Round]) -> Self {
+        let mut report = Report {
+            rounds: Vec::with_capacity(rounds.len()),
+            wins: 0,
+            ties: 0,
+            losses: 0,
+            shape_points: 0,
+            outcome_points: 0,
+            longest_win_streak: 0,
+            my_moves: [0; 3],
+            opponent_moves: [0; 3],
+        };
+        let mut streak = 0;
+        for &round in rounds {
+            let outcome = round.opponent.vs_my_move(round.me);
+            match outcome {
+                Outcome::Win => report.wins += 1,
+                Outcome::Tie => report.ties += 1,
+                Outcome::Loss => report.losses += 1,
+            }
+            streak = match outcome {
+                Outcome::Win => streak + 1,
+                _ => 0,
+            };
+            report.longest_win_streak = report.longest_win_streak.max(streak);
+            report.my_moves[round.me.index()] += 1;
+            report.opponent_moves[round.opponent.index()] += 1;
+
+            let shape_points = round.me.point_value();
+            let outcome_points = outcome.score();
+            report.shape_points += shape_points;
+            report.outcome_points += outcome_points;
+            report.rounds.push(RoundReport {
+                round,
+                outcome,
+                shape_points,
+                outcome_points,
+                running_score: report.score(),
+            });
+        }
+        report
+    }
+
+    /// My total score, as [`super::Strategy::run_and_score`] gives it.
+    pub fn score(&self) -> usize {
+        self.shape_points + self.outcome_points
+    }
+
+    /// A header, then a line per round with the moves, the outcome, the points and the running
+    /// score.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "round,opponent,me,outcome,shape_points,outcome_points,score,running_score\n",
+        );
+        for (i, r) in self.rounds.iter().enumerate() {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{}\n",
+                i + 1,
+                r.round.opponent.name(),
+                r.round.me.name(),
+                outcome_name(r.outcome),
+                r.shape_points,
+                r.outcome_points,
+                r.shape_points + r.outcome_points,
+                r.running_score
+            );
+        }
+        csv
+    }
+
+    /// A JSON object with the totals, and the running score after each round.
+    pub fn to_json(&self) -> String {
+        let moves = |counts: &[usize; 3]| {
+            let moves: Vec<_> = Move::ALL
+                .iter()
+                .zip(counts)
+                .map(|(&m, count)| format!(r#""{}":{count}"#, m.name()))
+                .collect();
+            format!("{{{}}}", moves.join(","))
+        };
+        let running: Vec<_> = self
+            .rounds
+            .iter()
+            .map(|r| r.running_score.to_string())
+            .collect();
+        format!(
+            r#"{{"rounds":{},"score":{},"wins":{},"ties":{},"losses":{},"shape_points":{},"outcome_points":{},"longest_win_streak":{},"my_moves":{},"opponent_moves":{},"running_score":[{}]}}"#,
+            self.rounds.len(),
+            self.score(),
+            self.wins,
+            self.ties,
+            self.losses,
+            self.shape_points,
+            self.outcome_points,
+            self.longest_win_streak,
+            moves(&self.my_moves),
+            moves(&self.opponent_moves),
+            running.join(",")
+        )
+    }
+}
+
+/// The totals, a line each, with the move counts for each player.
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "rounds          {}", self.rounds.len())?;
+        writeln!(
+            f,
+            "score           {} ({} from moves, {} from outcomes)",
+            self.score(),
+            self.shape_points,
+            self.outcome_points
+        )?;
+        writeln!(
+            f,
+            "record          {}-{}-{}",
+            self.wins, self.ties, self.losses
+        )?;
+        writeln!(f, "longest streak  {} wins", self.longest_win_streak)?;
+        for (side, counts) in [
+            ("my moves", &self.my_moves),
+            ("opponent moves", &self.opponent_moves),
+        ] {
+            let counts: Vec<_> = Move::ALL
+                .iter()
+                .zip(counts)
+                .map(|(&m, count)| format!("{count} {}", m.name()))
+                .collect();
+            writeln!(f, "{side:<15} {}", counts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// How to print a [`Report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Csv,
+    Json,
+}
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Format, anyhow::Error> {
+        match format {
+            "text" => Ok(Format::Text),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            other => Err(anyhow!("Invalid report format: {other}")),
+        }
+    }
+
+    pub fn render(self, report: &Report) -> String {
+        match self {
+            Format::Text => report.to_string(),
+            Format::Csv => report.to_csv(),
+            Format::Json => report.to_json() + "\n",
+        }
+    }
+}
+
+fn outcome_name(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Loss => "loss",
+        Outcome::Tie => "tie",
+        Outcome::Win => "win",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::{prop, prop_assert, prop_assert_eq, proptest};
+
+    use super::*;
+    use crate::rps::{tests::EXAMPLE, Decoding, Strategy};
+
+    #[test]
+    fn example() {
+        let strategy = Strategy::parse(EXAMPLE, &Decoding::literal_moves()).unwrap();
+        let report = Report::new(strategy.rounds());
+        assert_eq!(report.score(), 15);
+        assert_eq!((report.shape_points, report.outcome_points), (6, 9));
+        assert_eq!((report.wins, report.ties, report.losses), (1, 1, 1));
+        assert_eq!(report.longest_win_streak, 1);
+        assert_eq!(report.my_moves, [1, 1, 1]);
+        let running: Vec<usize> = report.rounds.iter().map(|r| r.running_score).collect();
+        assert_eq!(running, [8, 9, 15]);
+
+        assert_eq!(
+            report.to_csv(),
+            "round,opponent,me,outcome,shape_points,outcome_points,score,running_score\n\
+             1,rock,paper,win,2,6,8,8\n\
+             2,paper,rock,loss,1,0,1,9\n\
+             3,scissors,scissors,tie,3,3,6,15\n"
+        );
+        assert_eq!(
+            report.to_json(),
+            r#"{"rounds":3,"score":15,"wins":1,"ties":1,"losses":1,"shape_points":6,"outcome_points":9,"longest_win_streak":1,"my_moves":{"rock":1,"paper":1,"scissors":1},"opponent_moves":{"rock":1,"paper":1,"scissors":1},"running_score":[8,9,15]}"#
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn totals_agree(rounds in prop::collection::vec((0..3usize, 0..3usize), 0..100)) {
+            let rounds: Vec<Round> = rounds
+                .into_iter()
+                .map(|(opponent, me)| Round { opponent: Move::ALL[opponent], me: Move::ALL[me] })
+                .collect();
+            let report = Report::new(&rounds);
+            let score: usize = rounds.iter().map(Round::my_score).sum();
+            prop_assert_eq!(report.score(), score);
+            prop_assert_eq!(report.rounds.last().map_or(0, |r| r.running_score), score);
+            prop_assert_eq!(report.wins + report.ties + report.losses, rounds.len());
+            prop_assert_eq!(report.my_moves.iter().sum::<usize>(), rounds.len());
+            prop_assert_eq!(report.outcome_points, 6 * report.wins + 3 * report.ties);
+            prop_assert!(report.longest_win_streak <= report.wins);
+            prop_assert!(report.wins == 0 || report.longest_win_streak > 0);
+            prop_assert_eq!(report.to_csv().lines().count(), rounds.len() + 1);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::{fmt::Display, io::BufRead};
+
+use super::{Decoding, Outcome, Round};
+use crate::parse::Line;
+
+/// Running totals of a strategy guide, from my side.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Totals {
+    pub rounds: usize,
+    pub score: usize,
+    pub wins: usize,
+    pub ties: usize,
+    pub losses: usize,
+}
+
+impl Totals {
+    pub fn add(&mut self, round: &Round) {
+        self.rounds += 1;
+        self.score += round.my_score();
+        match round.opponent.vs_my_move(round.me) {
+            Outcome::Win => self.wins += 1,
+            Outcome::Tie => self.ties += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+    }
+}
+
+impl Display for Totals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} rounds, score {}, {}-{}-{}",
+            self.rounds, self.score, self.wins, self.ties, self.losses
+        )
+    }
+}
+
+/// Score a strategy guide a line at a time as it's read, without keeping the rounds, so the guide
+/// can be larger than memory. Every `every` rounds, `progress` is called with the totals so far,
+/// unless `every` is zero.
+pub fn score_lines(
+    mut reader: impl BufRead,
+    decoding: &Decoding,
+    every: usize,
+    mut progress: impl FnMut(&Totals),
+) -> Result<Totals, anyhow::Error> {
+    let mut totals = Totals::default();
+    let mut buffer = String::new();
+    for number in 1.. {
+        buffer.clear();
+        if reader.read_line(&mut buffer)? == 0 {
+            break;
+        }
+        // Lines end like they do for `str::lines`.
+        let text = match buffer.strip_suffix('\n') {
+            Some(text) => text.strip_suffix('\r').unwrap_or(text),
+            None => &buffer,
+        };
+        totals.add(&decoding.decode_line(&Line { number, text })?);
+        if every > 0 && totals.rounds.is_multiple_of(every) {
+            progress(&totals);
+        }
+    }
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::{prop, prop_assert_eq, proptest};
+
+    use super::*;
+    use crate::{
+        parse::{self, ParseError},
+        rps::{tests::EXAMPLE, Strategy, StrategyError},
+    };
+
+    #[test]
+    fn example() {
+        let mut partial = Vec::new();
+        let decoding = Decoding::literal_moves();
+        let totals = score_lines(EXAMPLE.as_bytes(), &decoding, 2, |t| partial.push(*t)).unwrap();
+        assert_eq!(totals.to_string(), "3 rounds, score 15, 1-1-1");
+        assert_eq!(partial.len(), 1);
+        assert_eq!(partial[0].score, 8 + 1);
+
+        let totals = score_lines(EXAMPLE.as_bytes(), &Decoding::desired_outcomes(), 0, |_| {
+            panic!("no progress was asked for")
+        });
+        assert_eq!(totals.unwrap().score, 12);
+    }
+
+    #[test]
+    fn invalid_line() {
+        let decoding = Decoding::desired_outcomes();
+        let data = "A Y\r\nB W\r\n";
+        let mut partial = Vec::new();
+        let error = score_lines(data.as_bytes(), &decoding, 1, |t| partial.push(*t)).unwrap_err();
+        let error = error.downcast_ref::<ParseError<StrategyError>>().unwrap();
+        assert_eq!(error.position, parse::Position { line: 2, column: 3 });
+        assert_eq!(error.found, "W");
+        assert_eq!(error.kind, StrategyError::MyColumn);
+        assert_eq!(error, &Strategy::parse(data, &decoding).unwrap_err());
+        // The first line was scored before the second turned out to be invalid.
+        assert_eq!(partial.len(), 1);
+    }
+
+    proptest! {
+        #[test]
+        fn matches_loading_the_guide(
+            lines in prop::collection::vec((0..3usize, 0..3usize), 0..100),
+            crlf: bool,
+            every in 0..10usize,
+        ) {
+            let end = if crlf { "\r\n" } else { "\n" };
+            let data: String = lines
+                .iter()
+                .map(|&(opponent, me)| {
+                    format!("{} {}{end}", b"ABC"[opponent] as char, b"XYZ"[me] as char)
+                })
+                .collect();
+            let decoding = Decoding::literal_moves();
+            let strategy = Strategy::parse(&data, &decoding).unwrap();
+
+            let mut calls = 0;
+            let totals = score_lines(data.as_bytes(), &decoding, every, |_| calls += 1).unwrap();
+            prop_assert_eq!(totals.score, strategy.run_and_score());
+            let report = strategy.report();
+            prop_assert_eq!(
+                (totals.rounds, totals.wins, totals.ties, totals.losses),
+                (report.rounds.len(), report.wins, report.ties, report.losses)
+            );
+            prop_assert_eq!(calls, lines.len().checked_div(every).unwrap_or(0));
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use std::{fmt::Display, fs::File, io::Read, path::PathBuf, str::FromStr};
+
+use super::{Move, Outcome};
+use crate::parse::{self, Line, ParseError};
+
+/// One of the moves of a [`Game`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameMove {
+    pub name: String,
+    /// Points for playing the move, whatever the outcome.
+    pub points: usize,
+}
+
+impl GameMove {
+    pub fn new(name: impl Into<String>, points: usize) -> Self {
+        GameMove {
+            name: name.into(),
+            points,
+        }
+    }
+}
+
+/// A round of a [`Game`], with both moves given by their index in [`Game::moves`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameRound {
+    pub opponent: usize,
+    pub me: usize,
+}
+
+impl GameRound {
+    /// The same round, from the other player's side.
+    pub fn flipped(self) -> GameRound {
+        GameRound {
+            opponent: self.me,
+            me: self.opponent,
+        }
+    }
+}
+
+/// A game like rock paper scissors, with an odd number of moves where every move beats exactly
+/// half of the others. Moves are referred to by their index in [`Game::moves`].
+///
+/// Games can be read from a list of moves with their points, then a blank line, then which move
+/// beats which:
+///
+/// ```text
+/// rock 1
+/// paper 2
+/// scissors 3
+///
+/// paper beats rock
+/// scissors beats paper
+/// rock beats scissors
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    moves: Vec<GameMove>,
+    /// `beats[a][b]` is true if move `a` beats move `b`.
+    beats: Vec<Vec<bool>>,
+}
+
+impl Game {
+    /// A game of `moves`, where each pair `(a, b)` in `beats` means move `a` beats move `b`. Every
+    /// pair of different moves must be decided exactly once.
+    pub fn new(moves: Vec<GameMove>, beats: &[(usize, usize)]) -> Result<Game, GameError> {
+        let mut game = Game {
+            beats: vec![vec![false; moves.len()]; moves.len()],
+            moves,
+        };
+        for (i, m) in game.moves.iter().enumerate() {
+            if game.moves[..i].iter().any(|other| other.name == m.name) {
+                return Err(GameError::DuplicateMove);
+            }
+        }
+        for &(winner, loser) in beats {
+            game.decide(winner, loser)?;
+        }
+        game.validate()?;
+        Ok(game)
+    }
+
+    /// A game where each move beats the half of the moves just before it, wrapping around. For
+    /// three moves, that's rock paper scissors.
+    pub fn cyclic(moves: Vec<GameMove>) -> Result<Game, GameError> {
+        let n = moves.len();
+        let beats: Vec<(usize, usize)> = (0..n)
+            .flat_map(|winner| (1..=n / 2).map(move |back| (winner, (winner + n - back) % n)))
+            .collect();
+        Game::new(moves, &beats)
+    }
+
+    /// Rock paper scissors, scored like the puzzle.
+    pub fn rock_paper_scissors() -> Game {
+        let moves = Move::ALL
+            .iter()
+            .map(|m| GameMove::new(m.name(), m.point_value()))
+            .collect();
+        Game::cyclic(moves).expect("rock paper scissors is a valid game")
+    }
+
+    /// Rock paper scissors lizard Spock, with moves worth 1 to 5 points in that order.
+    pub fn rock_paper_scissors_lizard_spock() -> Game {
+        let names = ["rock", "paper", "scissors", "lizard", "spock"];
+        let moves = names
+            .into_iter()
+            .zip(1..)
+            .map(|(name, points)| GameMove::new(name, points))
+            .collect();
+        let (rock, paper, scissors, lizard, spock) = (0, 1, 2, 3, 4);
+        let beats = [
+            (scissors, paper),
+            (paper, rock),
+            (rock, lizard),
+            (lizard, spock),
+            (spock, scissors),
+            (scissors, lizard),
+            (lizard, paper),
+            (paper, spock),
+            (spock, rock),
+            (rock, scissors),
+        ];
+        Game::new(moves, &beats).expect("rock paper scissors lizard Spock is a valid game")
+    }
+
+    pub fn load(file: impl Into<PathBuf>) -> Result<Game, anyhow::Error> {
+        Self::from_reader(File::open(file.into())?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Game, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
+    }
+
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    /// The index of the move called `name`.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.moves.iter().position(|m| m.name == name)
+    }
+
+    pub fn beats(&self, winner: usize, loser: usize) -> bool {
+        self.beats[winner][loser]
+    }
+
+    /// How playing `me` against `opponent` turns out for me.
+    pub fn outcome(&self, opponent: usize, me: usize) -> Outcome {
+        if self.beats(me, opponent) {
+            Outcome::Win
+        } else if self.beats(opponent, me) {
+            Outcome::Loss
+        } else {
+            Outcome::Tie
+        }
+    }
+
+    /// My score for a round, like [`super::Round::my_score`].
+    pub fn score(&self, opponent: usize, me: usize) -> usize {
+        self.moves[me].points + self.outcome(opponent, me).score()
+    }
+
+    /// Every move that beats `opponent`.
+    pub fn winning_responses(&self, opponent: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.moves.len()).filter(move |&m| self.beats(m, opponent))
+    }
+
+    /// The first move that beats `opponent`, or `opponent` itself in a game with a single move.
+    pub fn winning_response(&self, opponent: usize) -> usize {
+        self.winning_responses(opponent).next().unwrap_or(opponent)
+    }
+
+    /// Every move that loses to `opponent`.
+    pub fn losing_responses(&self, opponent: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..self.moves.len()).filter(move |&m| self.beats(opponent, m))
+    }
+
+    fn decide(&mut self, winner: usize, loser: usize) -> Result<(), GameError> {
+        let n = self.moves.len();
+        if winner >= n || loser >= n {
+            return Err(GameError::UnknownMove);
+        }
+        if winner == loser {
+            return Err(GameError::BeatsItself);
+        }
+        if self.beats[winner][loser] || self.beats[loser][winner] {
+            return Err(GameError::DecidedTwice);
+        }
+        self.beats[winner][loser] = true;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), GameError> {
+        let n = self.moves.len();
+        if n.is_multiple_of(2) {
+            return Err(GameError::EvenMoves);
+        }
+        for a in 0..n {
+            if (0..n).any(|b| a != b && !self.beats[a][b] && !self.beats[b][a]) {
+                return Err(GameError::Undecided);
+            }
+            if self.winning_responses(a).count() != n / 2 {
+                return Err(GameError::Unbalanced);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameError {
+    MoveName,
+    Points,
+    DuplicateMove,
+    Keyword(&'static str),
+    UnknownMove,
+    BeatsItself,
+    DecidedTwice,
+    EvenMoves,
+    Undecided,
+    Unbalanced,
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::MoveName => write!(f, "expected a move name"),
+            GameError::Points => write!(f, "expected the points for playing the move"),
+            GameError::DuplicateMove => write!(f, "expected every move name to be unique"),
+            GameError::Keyword(keyword) => write!(f, "expected `{keyword}`"),
+            GameError::UnknownMove => write!(f, "expected one of the game's moves"),
+            GameError::BeatsItself => write!(f, "expected a move to beat a different move"),
+            GameError::DecidedTwice => {
+                write!(f, "expected each pair of moves to be decided only once")
+            }
+            GameError::EvenMoves => write!(f, "expected an odd number of moves"),
+            GameError::Undecided => write!(f, "expected every pair of moves to be decided"),
+            GameError::Unbalanced => write!(f, "expected every move to beat half the others"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// The next word, and the index of the move it names.
+fn move_index<'a>(
+    line: &Line<'a>,
+    words: &mut impl Iterator<Item = &'a str>,
+    game: &Game,
+) -> Result<(&'a str, usize), ParseError<GameError>> {
+    let name = line.expect_word(words, GameError::UnknownMove)?;
+    match game.find(name) {
+        Some(index) => Ok((name, index)),
+        None => Err(line.error(name, GameError::UnknownMove)),
+    }
+}
+
+impl FromStr for Game {
+    type Err = ParseError<GameError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut lines = parse::lines(data);
+
+        let mut moves: Vec<GameMove> = Vec::new();
+        for line in lines.by_ref().take_while(|l| !l.text.is_empty()) {
+            let mut words = line.text.split_whitespace();
+            let name = line.expect_word(&mut words, GameError::MoveName)?;
+            if moves.iter().any(|m| m.name == name) {
+                return Err(line.error(name, GameError::DuplicateMove));
+            }
+            let points = line.expect_parsed(&mut words, GameError::Points)?;
+            moves.push(GameMove::new(name, points));
+        }
+
+        let mut game = Game {
+            beats: vec![vec![false; moves.len()]; moves.len()],
+            moves,
+        };
+        let mut last = None;
+        for line in lines {
+            let mut words = line.text.split_whitespace();
+            let (_, winner) = move_index(&line, &mut words, &game)?;
+            line.expect_keyword(&mut words, "beats", GameError::Keyword("beats"))?;
+            let (name, loser) = move_index(&line, &mut words, &game)?;
+            game.decide(winner, loser)
+                .map_err(|kind| line.error(name, kind))?;
+            last = Some(line);
+        }
+
+        game.validate().map_err(|kind| match last {
+            Some(line) => line.error_at_end(kind),
+            None => ParseError {
+                position: parse::Position { line: 1, column: 1 },
+                found: String::new(),
+                kind,
+            },
+        })?;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::{prop_assert_eq, proptest};
+
+    use super::*;
+    use crate::rps::Round;
+
+    #[test]
+    fn presets() {
+        let rpsls = Game::rock_paper_scissors_lizard_spock();
+        let spock = rpsls.find("spock").unwrap();
+        let beaten: Vec<&str> = rpsls
+            .losing_responses(spock)
+            .map(|m| rpsls.moves()[m].name.as_str())
+            .collect();
+        assert_eq!(beaten, ["rock", "scissors"]);
+        assert_eq!(rpsls.score(rpsls.find("lizard").unwrap(), spock), 5);
+
+        let data = "rock 1\npaper 2\nscissors 3\n\npaper beats rock\nscissors beats paper\nrock beats scissors\n";
+        assert_eq!(data.parse::<Game>().unwrap(), Game::rock_paper_scissors());
+    }
+
+    #[test]
+    fn invalid_games() {
+        let error = "a 1\nb 2\n\na beats b\n".parse::<Game>().unwrap_err();
+        assert_eq!(error.kind, GameError::EvenMoves);
+        assert_eq!(
+            error.position,
+            parse::Position {
+                line: 4,
+                column: 10
+            }
+        );
+
+        let error = "a 1\nb 2\nc 3\n\na beats b\nb beats a\n"
+            .parse::<Game>()
+            .unwrap_err();
+        assert_eq!(error.kind, GameError::DecidedTwice);
+        assert_eq!(error.position, parse::Position { line: 6, column: 9 });
+
+        let error = "a 1\nb 2\nc 3\n\na beats d\n".parse::<Game>().unwrap_err();
+        assert_eq!(error.kind, GameError::UnknownMove);
+        assert_eq!(error.found, "d");
+
+        let error = "a 1\nb 2\nc 3\n\na beats b\nb beats c\na beats c\n"
+            .parse::<Game>()
+            .unwrap_err();
+        assert_eq!(error.kind, GameError::Unbalanced);
+
+        let moves = vec![
+            GameMove::new("a", 1),
+            GameMove::new("a", 2),
+            GameMove::new("b", 3),
+        ];
+        assert_eq!(Game::cyclic(moves), Err(GameError::DuplicateMove));
+    }
+
+    proptest! {
+        #[test]
+        fn preset_matches_puzzle_scoring(opponent in 0..3usize, me in 0..3usize) {
+            let game = Game::rock_paper_scissors();
+            let round = Round { opponent: Move::ALL[opponent], me: Move::ALL[me] };
+            prop_assert_eq!(game.score(opponent, me), round.my_score());
+        }
+
+        #[test]
+        fn cyclic_games_are_balanced(half in 0..8usize, a in 0..17usize, b in 0..17usize) {
+            let n = 2 * half + 1;
+            let moves = (0..n).map(|i| GameMove::new(i.to_string(), i)).collect();
+            let game = Game::cyclic(moves).unwrap();
+            let (a, b) = (a % n, b % n);
+            prop_assert_eq!(game.outcome(a, b).score() + game.outcome(b, a).score(), 6);
+            prop_assert_eq!(game.winning_responses(a).count(), half);
+        }
+    }
+}
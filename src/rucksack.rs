@@ -1,4 +1,11 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, fmt::Display, fs::File, io::Read, path::PathBuf, str::FromStr};
+
+use anyhow::anyhow;
+
+use crate::{
+    parse::{self, ParseError},
+    solution::Solution,
+};
 
 #[derive(Debug, Default)]
 pub struct Rucksack {
@@ -6,6 +13,7 @@ pub struct Rucksack {
     pub right: Vec<char>,
 }
 
+#[derive(Debug)]
 pub struct RucksackInventory {
     pub list: Vec<Rucksack>,
     pub priority_map: HashMap<char, usize>,
@@ -18,36 +26,47 @@ pub struct RucksackAnalysis {
 
 impl RucksackInventory {
     pub fn load(file: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
-        let mut list = Vec::new();
-        let data = std::fs::read_to_string(file.into())?;
-        for line in data.lines() {
-            let (l, r) = line.split_at(line.len() / 2);
-            let rucksack = Rucksack {
-                left: l.chars().collect(),
-                right: r.chars().collect(),
-            };
-            list.push(rucksack);
-        }
-        let priority_map = Self::priority_map();
-        Ok(RucksackInventory { list, priority_map })
+        Self::from_reader(File::open(file.into())?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
     }
 
-    pub fn analyze_rucksack(&self) -> RucksackAnalysis {
+    /// The item in both compartments of each rucksack, and the badge of each group of three. It's
+    /// an error for a rucksack to have no item in both compartments, for the rucksacks not to split
+    /// into groups of three, or for a group to have no badge.
+    pub fn analyze_rucksack(&self) -> Result<RucksackAnalysis, anyhow::Error> {
         let mut errors = Vec::new();
+        for (i, sack) in self.list.iter().enumerate() {
+            let error = sack
+                .left
+                .iter()
+                .find(|c| sack.right.contains(c))
+                .ok_or_else(|| anyhow!("Rucksack {} has no item in both compartments", i + 1))?;
+            errors.push(*error);
+        }
+        if !self.list.len().is_multiple_of(3) {
+            return Err(anyhow!(
+                "Rucksack {} is not in a group of three",
+                self.list.len() / 3 * 3 + 1
+            ));
+        }
         let mut badges = Vec::new();
-        for group in self.list.chunks_exact(3) {
-            for sack in group.iter() {
-                let error = sack.left.iter().find(|c| sack.right.contains(c)).unwrap();
-                errors.push(*error);
-            }
-            let badge = group[0].left.iter().chain(group[0].right.iter()).find(|c| {
-                let elf_2 = group[1].left.contains(c) || group[1].right.contains(c);
-                let elf_3 = group[2].left.contains(c) || group[2].right.contains(c);
-                elf_2 && elf_3
-            }).unwrap();
+        for (i, group) in self.list.chunks_exact(3).enumerate() {
+            let badge = group[0]
+                .left
+                .iter()
+                .chain(group[0].right.iter())
+                .find(|c| {
+                    let elf_2 = group[1].left.contains(c) || group[1].right.contains(c);
+                    let elf_3 = group[2].left.contains(c) || group[2].right.contains(c);
+                    elf_2 && elf_3
+                })
+                .ok_or_else(|| anyhow!("Group {} has no badge", i + 1))?;
             badges.push(*badge);
         }
-        RucksackAnalysis { errors, badges }
+        Ok(RucksackAnalysis { errors, badges })
     }
 
     pub fn sum_priorities(&self, chars: Vec<char>) -> usize {
@@ -71,3 +90,182 @@ impl RucksackInventory {
         .collect()
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RucksackError {
+    InvalidItem,
+    OddItemCount,
+}
+
+impl Display for RucksackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RucksackError::InvalidItem => write!(f, "expected an item (a-z or A-Z)"),
+            RucksackError::OddItemCount => {
+                write!(f, "expected the same number of items in both compartments")
+            }
+        }
+    }
+}
+
+impl FromStr for RucksackInventory {
+    type Err = ParseError<RucksackError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut list = Vec::new();
+        for line in parse::lines(data) {
+            if let Some(item) = line.text.matches(|c: char| !c.is_ascii_alphabetic()).next() {
+                return Err(line.error(item, RucksackError::InvalidItem));
+            }
+            if line.text.len() % 2 != 0 {
+                return Err(line.error(line.text, RucksackError::OddItemCount));
+            }
+            let (l, r) = line.text.split_at(line.text.len() / 2);
+            let rucksack = Rucksack {
+                left: l.chars().collect(),
+                right: r.chars().collect(),
+            };
+            list.push(rucksack);
+        }
+        let priority_map = Self::priority_map();
+        Ok(RucksackInventory { list, priority_map })
+    }
+}
+
+pub struct RucksackReorganization;
+
+impl Solution for RucksackReorganization {
+    const DAY: u8 = 3;
+
+    type Input = RucksackInventory;
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error> {
+        Ok(input.parse()?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.sum_priorities(input.analyze_rucksack()?.errors))
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.sum_priorities(input.analyze_rucksack()?.badges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use proptest::{prelude::*, sample::Index};
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
+
+    fn item() -> impl Strategy<Value = char> {
+        let items: Vec<char> = ('a'..='z').chain('A'..='Z').collect();
+        prop::sample::select(items)
+    }
+
+    /// Groups of three rucksacks, where every rucksack has a shared item between its
+    /// compartments, and every group shares a badge.
+    fn groups() -> impl Strategy<Value = Vec<String>> {
+        let sack = (prop::collection::vec(item(), 1..10), any::<Index>());
+        let group = (prop::collection::vec(sack, 3), item());
+        prop::collection::vec(group, 1..10).prop_map(|groups| {
+            let mut sacks = Vec::new();
+            for (group, badge) in groups {
+                for (mut left, index) in group {
+                    let mut right: Vec<char> = left.iter().rev().copied().collect();
+                    left[0] = badge;
+                    right[0] = *index.get(&left);
+                    sacks.push(left.into_iter().chain(right).collect());
+                }
+            }
+            sacks
+        })
+    }
+
+    #[test]
+    fn example() {
+        let inventory: RucksackInventory = EXAMPLE.parse().unwrap();
+        assert_eq!(RucksackReorganization::part1(&inventory).unwrap(), 157);
+        assert_eq!(RucksackReorganization::part2(&inventory).unwrap(), 70);
+    }
+
+    #[test]
+    fn invalid_items() {
+        let error = "abcb\nab-a\n".parse::<RucksackInventory>().unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 2, column: 3 });
+        assert_eq!(error.kind, RucksackError::InvalidItem);
+
+        let error = "abcb\nabc\n".parse::<RucksackInventory>().unwrap_err();
+        assert_eq!(error.kind, RucksackError::OddItemCount);
+    }
+
+    #[test]
+    fn nothing_shared() {
+        let inventory: RucksackInventory = "abcd\nefgh\nijkl\n".parse().unwrap();
+        let error = RucksackReorganization::part1(&inventory).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Rucksack 1 has no item in both compartments"
+        );
+
+        let inventory: RucksackInventory = "abca\ndefd\nghig\n".parse().unwrap();
+        let error = RucksackReorganization::part2(&inventory).unwrap_err();
+        assert_eq!(error.to_string(), "Group 1 has no badge");
+    }
+
+    #[test]
+    fn incomplete_group() {
+        let inventory: RucksackInventory = format!("{EXAMPLE}abcd\n").parse().unwrap();
+        let error = RucksackReorganization::part1(&inventory).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Rucksack 7 has no item in both compartments"
+        );
+
+        let inventory: RucksackInventory = format!("{EXAMPLE}abca\nabcb\n").parse().unwrap();
+        let error = RucksackReorganization::part1(&inventory).unwrap_err();
+        assert_eq!(error.to_string(), "Rucksack 7 is not in a group of three");
+    }
+
+    proptest! {
+        #[test]
+        fn priorities_match_char_arithmetic(items in prop::collection::vec(item(), 0..50)) {
+            let expected: usize = items
+                .iter()
+                .map(|&c| match c {
+                    'a'..='z' => c as usize - 'a' as usize + 1,
+                    _ => c as usize - 'A' as usize + 27,
+                })
+                .sum();
+            let inventory: RucksackInventory = "".parse().unwrap();
+            prop_assert_eq!(inventory.sum_priorities(items), expected);
+        }
+
+        #[test]
+        fn analysis_matches_set_intersections(sacks in groups()) {
+            let inventory: RucksackInventory = sacks.join("\n").parse().unwrap();
+            let analysis = inventory.analyze_rucksack().unwrap();
+
+            let set = |items: &[char]| items.iter().copied().collect::<HashSet<_>>();
+            for (sack, error) in inventory.list.iter().zip(analysis.errors.iter()) {
+                prop_assert!(set(&sack.left).intersection(&set(&sack.right)).any(|c| c == error));
+            }
+            for (group, badge) in inventory.list.chunks_exact(3).zip(analysis.badges.iter()) {
+                let all = |sack: &Rucksack| set(&[sack.left.as_slice(), &sack.right].concat());
+                prop_assert!(group.iter().all(|sack| all(sack).contains(badge)));
+            }
+        }
+    }
+}
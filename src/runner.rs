@@ -0,0 +1,98 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+
+use crate::{
+    bench::{bench, Timings},
+    calories::CalorieCounting,
+    filesystem::NoSpaceLeft,
+    rps::RockPaperScissors,
+    rucksack::RucksackReorganization,
+    sections::CampCleanup,
+    signal::TuningTrouble,
+    solution::{parse_and_solve, Solution},
+    warehouse::SupplyStacks,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const ALL: [Part; 2] = [Part::One, Part::Two];
+
+    pub fn number(self) -> u8 {
+        match self {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+/// Every solver takes the puzzle input and the part to solve, and returns the answer ready to be
+/// printed.
+pub type SolverFn = fn(&str, Part) -> Result<String, anyhow::Error>;
+
+/// Times each phase of a day's solution on the given puzzle input, repeated some number of times.
+pub type BenchFn = fn(&str, usize) -> Result<Timings, anyhow::Error>;
+
+pub struct Day {
+    pub day: u8,
+    /// File name of the default input, relative to [`INPUT_DIR`].
+    pub input: &'static str,
+    pub solver: SolverFn,
+    pub bench: BenchFn,
+}
+
+impl Day {
+    pub const fn of<S: Solution>(input: &'static str) -> Self {
+        Day {
+            day: S::DAY,
+            input,
+            solver: parse_and_solve::<S>,
+            bench: bench::<S>,
+        }
+    }
+
+    pub fn default_input(&self) -> PathBuf {
+        Path::new(INPUT_DIR).join(self.input)
+    }
+}
+
+pub const INPUT_DIR: &str = "./inputs";
+
+pub const DAYS: &[Day] = &[
+    Day::of::<CalorieCounting>("calories.txt"),
+    Day::of::<RockPaperScissors>("rps_strategy.txt"),
+    Day::of::<RucksackReorganization>("rucksack_list.txt"),
+    Day::of::<CampCleanup>("sections.txt"),
+    Day::of::<SupplyStacks>("crates.txt"),
+    Day::of::<TuningTrouble>("signal.txt"),
+    Day::of::<NoSpaceLeft>("filesystem.txt"),
+];
+
+pub fn find_day(day: u8) -> Result<&'static Day, anyhow::Error> {
+    DAYS.iter()
+        .find(|d| d.day == day)
+        .ok_or_else(|| anyhow!("No solver registered for day {day}"))
+}
+
+pub struct Answer {
+    pub answer: String,
+    pub elapsed: Duration,
+}
+
+pub fn run(day: &Day, part: Part, input: &Path) -> Result<Answer, anyhow::Error> {
+    let data = std::fs::read_to_string(input)?;
+    let start = Instant::now();
+    let answer = (day.solver)(&data, part)?;
+    Ok(Answer {
+        answer,
+        elapsed: start.elapsed(),
+    })
+}
@@ -1,4 +1,9 @@
-use std::path::PathBuf;
+use std::{fmt::Display, fs::File, io::Read, ops::RangeInclusive, path::PathBuf, str::FromStr};
+
+use crate::{
+    parse::{self, Line, ParseError},
+    solution::Solution,
+};
 
 #[derive(Debug, Clone)]
 pub struct Sections {
@@ -7,27 +12,11 @@ pub struct Sections {
 
 impl Sections {
     pub fn load(file: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
-        let mut pairs = Vec::new();
-        let data = std::fs::read_to_string(file.into())?;
-        for line in data.lines() {
-            let (elf_1, elf_2) = line.split_once(',').unwrap();
-            let elf_1 = elf_1.split_once('-').unwrap();
-            let elf_1 = (elf_1.0.parse().unwrap(), elf_1.1.parse().unwrap());
-            let elf_2 = elf_2.split_once('-').unwrap();
-            let elf_2 = (elf_2.0.parse().unwrap(), elf_2.1.parse().unwrap());
-            pairs.push((elf_1.0..=elf_1.1, elf_2.0..=elf_2.1));
-        }
-        // Convert from ranges to bit masks, for example:
-        // 2..3 -> 01100000 -> 96
-        let pairs = pairs
-            .drain(..)
-            .map(|(r1, r2)| {
-                let r1 = r1.map(|i: u32| 2u128.pow(i)).reduce(|a, x| a + x).unwrap();
-                let r2 = r2.map(|i: u32| 2u128.pow(i)).reduce(|a, x| a + x).unwrap();
-                (r1, r2)
-            })
-            .collect();
-        Ok(Sections { pairs })
+        Self::from_reader(File::open(file.into())?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
     }
 
     pub fn fully_contained(&self) -> impl Iterator<Item=&(u128, u128)> + '_ {
@@ -47,3 +36,145 @@ impl Sections {
             })
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionError {
+    MissingComma,
+    MissingDash,
+    InvalidSection,
+    InvertedRange,
+}
+
+impl Display for SectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SectionError::MissingComma => write!(f, "expected two comma separated ranges"),
+            SectionError::MissingDash => write!(f, "expected a range of sections, like 2-4"),
+            SectionError::InvalidSection => {
+                write!(f, "expected a section number from 0 to {}", u128::BITS - 1)
+            }
+            SectionError::InvertedRange => write!(f, "expected the range to start before it ends"),
+        }
+    }
+}
+
+/// Parse a single elf's `start-end` range, which must fit in the `u128` bit mask.
+fn parse_range(line: &Line, text: &str) -> Result<RangeInclusive<u32>, ParseError<SectionError>> {
+    let (start, end) = text
+        .split_once('-')
+        .ok_or_else(|| line.error(text, SectionError::MissingDash))?;
+    let section = |s: &str| {
+        s.parse()
+            .ok()
+            .filter(|&i| i < u128::BITS)
+            .ok_or_else(|| line.error(s, SectionError::InvalidSection))
+    };
+    let (start, end) = (section(start)?, section(end)?);
+    if start > end {
+        return Err(line.error(text, SectionError::InvertedRange));
+    }
+    Ok(start..=end)
+}
+
+impl FromStr for Sections {
+    type Err = ParseError<SectionError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut pairs = Vec::new();
+        for line in parse::lines(data) {
+            let (elf_1, elf_2) = line
+                .text
+                .split_once(',')
+                .ok_or_else(|| line.error(line.text, SectionError::MissingComma))?;
+            pairs.push((parse_range(&line, elf_1)?, parse_range(&line, elf_2)?));
+        }
+        // Convert from ranges to bit masks, for example:
+        // 2..3 -> 01100000 -> 96
+        let pairs = pairs
+            .drain(..)
+            .map(|(r1, r2)| {
+                let r1 = r1.map(|i: u32| 2u128.pow(i)).reduce(|a, x| a + x).unwrap();
+                let r2 = r2.map(|i: u32| 2u128.pow(i)).reduce(|a, x| a + x).unwrap();
+                (r1, r2)
+            })
+            .collect();
+        Ok(Sections { pairs })
+    }
+}
+
+pub struct CampCleanup;
+
+impl Solution for CampCleanup {
+    const DAY: u8 = 4;
+
+    type Input = Sections;
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error> {
+        Ok(input.parse()?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.fully_contained().count())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        Ok(input.overlapped().count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const EXAMPLE: &str = "\
+2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+";
+
+    fn range() -> impl Strategy<Value = (u32, u32)> {
+        (0..u128::BITS, 0..u128::BITS).prop_map(|(a, b)| (a.min(b), a.max(b)))
+    }
+
+    #[test]
+    fn example() {
+        let sections: Sections = EXAMPLE.parse().unwrap();
+        assert_eq!(CampCleanup::part1(&sections).unwrap(), 2);
+        assert_eq!(CampCleanup::part2(&sections).unwrap(), 4);
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        let error = "2-4,6-8\n2-4,6-128\n".parse::<Sections>().unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 2, column: 7 });
+        assert_eq!(error.kind, SectionError::InvalidSection);
+
+        let error = "4-2,6-8\n".parse::<Sections>().unwrap_err();
+        assert_eq!(error.kind, SectionError::InvertedRange);
+    }
+
+    proptest! {
+        #[test]
+        fn bit_masks_match_range_comparisons(pairs in prop::collection::vec((range(), range()), 0..50)) {
+            let data: String = pairs
+                .iter()
+                .map(|((a, b), (c, d))| format!("{a}-{b},{c}-{d}\n"))
+                .collect();
+            let sections: Sections = data.parse().unwrap();
+
+            let contained = pairs
+                .iter()
+                .filter(|((a, b), (c, d))| (a <= c && d <= b) || (c <= a && b <= d))
+                .count();
+            let overlapped = pairs.iter().filter(|((a, b), (c, d))| a <= d && c <= b).count();
+            prop_assert_eq!(sections.fully_contained().count(), contained);
+            prop_assert_eq!(sections.overlapped().count(), overlapped);
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use std::path::PathBuf;
+use std::{convert::Infallible, fs::File, io::Read, path::PathBuf, str::FromStr};
+
+use anyhow::anyhow;
+
+use crate::solution::Solution;
 
 pub struct Signal {
     data: Vec<char>,
@@ -6,27 +10,118 @@ pub struct Signal {
 
 impl Signal {
     pub fn load(file: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
-        let data = std::fs::read_to_string(file.into())?.chars().collect();
+        Self::from_reader(File::open(file.into())?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
+    }
+
+    /// The position just past every run of `run_length` different characters. There are none if
+    /// the signal is shorter than a run, and runs must be at least one character long.
+    pub fn markers(
+        &self,
+        run_length: usize,
+    ) -> Result<impl Iterator<Item = usize> + '_, anyhow::Error> {
+        if run_length == 0 {
+            return Err(anyhow!("A marker must be at least one character long"));
+        }
+        Ok(self
+            .data
+            .windows(run_length)
+            .enumerate()
+            .filter(|(_, window)| {
+                window
+                    .iter()
+                    .enumerate()
+                    .all(|(i, c)| !window[i + 1..].contains(c))
+            })
+            .map(move |(i, _)| i + run_length))
+    }
+}
+
+impl FromStr for Signal {
+    /// Any text is a valid signal.
+    type Err = Infallible;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let data = data.chars().collect();
         Ok(Signal { data })
     }
+}
+
+pub struct TuningTrouble;
+
+impl Solution for TuningTrouble {
+    const DAY: u8 = 6;
+
+    type Input = Signal;
+    type Answer = usize;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error> {
+        Ok(input.parse()?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        input
+            .markers(4)?
+            .next()
+            .ok_or_else(|| anyhow!("No start-of-packet marker found"))
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        input
+            .markers(14)?
+            .next()
+            .ok_or_else(|| anyhow!("No start-of-message marker found"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
 
-    pub fn markers(&self, run_length: usize) -> impl Iterator<Item = usize> + '_ {
-        let mut kernel = std::collections::VecDeque::new();
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const EXAMPLES: [(&str, usize, usize); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn examples() {
+        for (data, packet, message) in EXAMPLES {
+            let signal: Signal = data.parse().unwrap();
+            assert_eq!(TuningTrouble::part1(&signal).unwrap(), packet, "{data}");
+            assert_eq!(TuningTrouble::part2(&signal).unwrap(), message, "{data}");
+        }
+    }
+
+    #[test]
+    fn short_signals() {
+        let signal: Signal = "abc".parse().unwrap();
+        let error = TuningTrouble::part1(&signal).unwrap_err();
+        assert_eq!(error.to_string(), "No start-of-packet marker found");
+        assert!(signal.markers(0).is_err());
+    }
 
-        let mut data = self.data.iter().enumerate();
-        for _ in 0..run_length - 1 {
-            kernel.push_front(data.next().unwrap())
+    proptest! {
+        #[test]
+        fn markers_match_hash_set_windows(data in "[a-p]{0,200}", run_length in 1..=14usize) {
+            let signal: Signal = data.parse().unwrap();
+            let chars: Vec<char> = data.chars().collect();
+            let expected: Vec<usize> = chars
+                .windows(run_length)
+                .enumerate()
+                .filter(|(_, window)| window.iter().collect::<HashSet<_>>().len() == run_length)
+                .map(|(i, _)| i + run_length)
+                .collect();
+            prop_assert_eq!(signal.markers(run_length).unwrap().collect::<Vec<_>>(), expected);
         }
-        data.filter_map(move |input| {
-            kernel.push_front(input);
-            for char in kernel.iter() {
-                if kernel.iter().filter(|c| c.1 == char.1).count() > 1 {
-                    kernel.pop_back();
-                    return None;
-                }
-            }
-            kernel.pop_back();
-            Some(input.0 + 1)
-        })
     }
 }
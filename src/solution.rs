@@ -0,0 +1,32 @@
+use std::fmt::Display;
+
+use crate::runner::Part;
+
+/// A single day's puzzle, split into an input parsing phase and the two solving phases.
+pub trait Solution {
+    const DAY: u8;
+
+    /// The parsed puzzle input, shared by both parts.
+    type Input;
+    type Answer: Display;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error>;
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error>;
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error>;
+
+    fn solve(input: &Self::Input, part: Part) -> Result<Self::Answer, anyhow::Error> {
+        match part {
+            Part::One => Self::part1(input),
+            Part::Two => Self::part2(input),
+        }
+    }
+}
+
+/// Parse `input` and solve `part`, erasing the solution's types so every day can be called the
+/// same way.
+pub fn parse_and_solve<S: Solution>(input: &str, part: Part) -> Result<String, anyhow::Error> {
+    let input = S::parse(input)?;
+    Ok(S::solve(&input, part)?.to_string())
+}
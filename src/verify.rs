@@ -0,0 +1,421 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    fetch,
+    parse::{self, ParseError},
+    runner::{self, Day, Part},
+};
+
+pub const ANSWERS_FILE: &str = "./answers.toml";
+
+/// The known-correct answers for one input file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expected {
+    /// File name of the input, relative to [`runner::INPUT_DIR`].
+    pub input: String,
+    pub day: u8,
+    pub part1: Option<String>,
+    pub part2: Option<String>,
+}
+
+impl Expected {
+    pub fn part(&self, part: Part) -> Option<&str> {
+        match part {
+            Part::One => self.part1.as_deref(),
+            Part::Two => self.part2.as_deref(),
+        }
+    }
+}
+
+/// The contents of `answers.toml`: a table per input file, naming the day that solves it and the
+/// expected answer to each part.
+///
+/// ```toml
+/// ["crates.txt"]
+/// day = 5
+/// part1 = "VWLCWGSDQ"
+/// part2 = "TCGLQSLPW"
+/// ```
+///
+/// Only this small subset of TOML is understood: quoted table names, `#` comments, and string or
+/// integer values. Strings may use the escapes `\"`, `\\`, `\n`, `\r`, `\t` and `\uXXXX`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Answers {
+    pub expected: Vec<Expected>,
+}
+
+impl Answers {
+    pub fn load(file: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        Self::from_reader(File::open(file.into())?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
+    }
+
+    /// Record the current answers of every registered day, solving its default input. Missing
+    /// inputs are downloaded, as [`fetch::default_input`] does.
+    pub fn record(days: &[Day]) -> Result<Self, anyhow::Error> {
+        let mut expected = Vec::new();
+        for day in days {
+            let input = fetch::default_input(day)?;
+            expected.push(Expected {
+                input: day.input.to_owned(),
+                day: day.day,
+                part1: Some(runner::run(day, Part::One, &input)?.answer),
+                part2: Some(runner::run(day, Part::Two, &input)?.answer),
+            });
+        }
+        Ok(Answers { expected })
+    }
+}
+
+impl Display for Answers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# Known-correct answers, checked by `aoc22 verify`.")?;
+        for expected in self.expected.iter() {
+            writeln!(f)?;
+            writeln!(f, "[{}]", quote(&expected.input))?;
+            writeln!(f, "day = {}", expected.day)?;
+            if let Some(answer) = &expected.part1 {
+                writeln!(f, "part1 = {}", quote(answer))?;
+            }
+            if let Some(answer) = &expected.part2 {
+                writeln!(f, "part2 = {}", quote(answer))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnswersError {
+    Table,
+    KeyOutsideTable,
+    Assignment,
+    Key,
+    Value,
+    Day,
+    MissingDay,
+}
+
+impl Display for AnswersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnswersError::Table => write!(f, "expected a quoted table name, like [\"crates.txt\"]"),
+            AnswersError::KeyOutsideTable => write!(f, "expected a table before any keys"),
+            AnswersError::Assignment => write!(f, "expected `key = value`"),
+            AnswersError::Key => write!(f, "expected `day`, `part1` or `part2`"),
+            AnswersError::Value => write!(f, "expected a quoted string or an integer"),
+            AnswersError::Day => write!(f, "expected a day number"),
+            AnswersError::MissingDay => write!(f, "expected the table to set `day`"),
+        }
+    }
+}
+
+impl FromStr for Answers {
+    type Err = ParseError<AnswersError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut expected: Vec<Expected> = Vec::new();
+        let mut headers = Vec::new();
+
+        for line in parse::lines(data) {
+            let text = line.text.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+
+            if let Some(table) = text.strip_prefix('[') {
+                let name = table
+                    .strip_suffix(']')
+                    .and_then(|name| unquote(name.trim()))
+                    .ok_or_else(|| line.error(text, AnswersError::Table))?;
+                expected.push(Expected {
+                    input: name,
+                    day: 0,
+                    part1: None,
+                    part2: None,
+                });
+                headers.push(line);
+                continue;
+            }
+
+            let entry = expected
+                .last_mut()
+                .ok_or_else(|| line.error(text, AnswersError::KeyOutsideTable))?;
+            let (key, value) = text
+                .split_once('=')
+                .ok_or_else(|| line.error(text, AnswersError::Assignment))?;
+            let (key, value) = (key.trim(), value.trim());
+            let answer = match unquote(value) {
+                Some(string) => string,
+                None if value.parse::<i64>().is_ok() => value.to_owned(),
+                None => return Err(line.error(value, AnswersError::Value)),
+            };
+            match key {
+                "day" => {
+                    entry.day = value
+                        .parse()
+                        .ok()
+                        .filter(|&day| day > 0)
+                        .ok_or_else(|| line.error(value, AnswersError::Day))?
+                }
+                "part1" => entry.part1 = Some(answer),
+                "part2" => entry.part2 = Some(answer),
+                _ => return Err(line.error(key, AnswersError::Key)),
+            }
+        }
+        for (entry, header) in expected.iter().zip(headers) {
+            if entry.day == 0 {
+                return Err(header.error(header.text, AnswersError::MissingDay));
+            }
+        }
+
+        Ok(Answers { expected })
+    }
+}
+
+/// `text` in double quotes, escaping quotes, backslashes and control characters so that
+/// [`unquote`] gives it back.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The contents of a double quoted string, with its escapes replaced, or `None` if it isn't one.
+fn unquote(text: &str) -> Option<String> {
+    let inner = text.strip_prefix('"')?.strip_suffix('"')?;
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            '"' => return None,
+            '\\' => match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                        return None;
+                    }
+                    char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                }
+                _ => return None,
+            },
+            c => c,
+        };
+        unquoted.push(c);
+    }
+    Some(unquoted)
+}
+
+/// The result of checking one answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub day: u8,
+    pub part: Part,
+    pub input: String,
+    pub expected: String,
+    /// The solver's answer, or the error it failed with.
+    pub actual: Result<String, String>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.actual.as_ref() == Ok(&self.expected)
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = if self.passed() { "ok" } else { "FAIL" };
+        write!(
+            f,
+            "day {} part {} ({}): {status}",
+            self.day,
+            self.part.number(),
+            self.input
+        )?;
+        if !self.passed() {
+            write!(f, "\n    - {}", self.expected)?;
+            match &self.actual {
+                Ok(actual) => write!(f, "\n    + {actual}")?,
+                Err(error) => write!(f, "\n    ! {error}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Solve every input listed in `answers`, and compare against the expected answers. A day's
+/// default input is downloaded if it's missing, as [`fetch::default_input`] does.
+pub fn verify(answers: &Answers) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for expected in answers.expected.iter() {
+        // Found once for both parts, so a missing input is only downloaded once.
+        let input = runner::find_day(expected.day)
+            .and_then(|day| Ok((day, input_path(day, &expected.input)?)))
+            .map_err(|e| e.to_string());
+        for part in Part::ALL {
+            let Some(answer) = expected.part(part) else {
+                continue;
+            };
+            let actual = input
+                .clone()
+                .and_then(|(day, input)| runner::run(day, part, &input).map_err(|e| e.to_string()))
+                .map(|result| result.answer);
+            checks.push(Check {
+                day: expected.day,
+                part,
+                input: expected.input.clone(),
+                expected: answer.to_owned(),
+                actual,
+            });
+        }
+    }
+    checks
+}
+
+/// Where to find `input` for `day`: the day's default input comes from [`fetch::default_input`],
+/// and any other input from [`runner::INPUT_DIR`].
+fn input_path(day: &Day, input: &str) -> Result<PathBuf, anyhow::Error> {
+    if input == day.input {
+        return fetch::default_input(day);
+    }
+    Ok(Path::new(runner::INPUT_DIR).join(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const EXAMPLE: &str = r#"
+# Comments and blank lines are skipped.
+["crates.txt"]
+day = 5
+part1 = "CMZ"
+part2 = "MCD"
+
+[ "calories.txt" ]
+  day=1
+part1 = 24000
+"#;
+
+    #[test]
+    fn example() {
+        let answers: Answers = EXAMPLE.parse().unwrap();
+        assert_eq!(
+            answers.expected,
+            [
+                Expected {
+                    input: "crates.txt".into(),
+                    day: 5,
+                    part1: Some("CMZ".into()),
+                    part2: Some("MCD".into()),
+                },
+                Expected {
+                    input: "calories.txt".into(),
+                    day: 1,
+                    part1: Some("24000".into()),
+                    part2: None,
+                },
+            ]
+        );
+        assert_eq!(answers.expected[1].part(Part::Two), None);
+    }
+
+    #[test]
+    fn escapes() {
+        let data = r#"["a \"b\" \\ c"]
+day = 1
+part1 = " two\nlines\t"
+part2 = "\u00e9\u0007"
+"#;
+        let answers: Answers = data.parse().unwrap();
+        let expected = &answers.expected[0];
+        assert_eq!(expected.input, r#"a "b" \ c"#);
+        assert_eq!(expected.part1.as_deref(), Some(" two\nlines\t"));
+        assert_eq!(expected.part2.as_deref(), Some("\u{e9}\u{7}"));
+        assert_eq!(answers.to_string().parse::<Answers>().unwrap(), answers);
+
+        for value in [r#""a"b""#, r#""a\""#, r#""\q""#, r#""\u12""#, r#""\uD800""#] {
+            let data = format!("[\"a\"]\nday = 1\npart1 = {value}\n");
+            let error = data.parse::<Answers>().unwrap_err();
+            assert_eq!(error.kind, AnswersError::Value, "{value}");
+        }
+    }
+
+    #[test]
+    fn invalid_answers() {
+        let cases = [
+            ("[crates.txt]\n", AnswersError::Table, 1, 1),
+            ("day = 5\n", AnswersError::KeyOutsideTable, 1, 1),
+            ("[\"a\"]\nday 5\n", AnswersError::Assignment, 2, 1),
+            ("[\"a\"]\nday = 5\npart3 = \"x\"\n", AnswersError::Key, 3, 1),
+            ("[\"a\"]\nday = 5\npart1 = x\n", AnswersError::Value, 3, 9),
+            ("[\"a\"]\nday = \"five\"\n", AnswersError::Day, 2, 7),
+            ("[\"a\"]\nday = 0\n", AnswersError::Day, 2, 7),
+            (
+                "[\"a\"]\nday = 1\n[\"b\"]\npart1 = \"x\"\n",
+                AnswersError::MissingDay,
+                3,
+                1,
+            ),
+        ];
+        for (data, kind, line, column) in cases {
+            let error = data.parse::<Answers>().unwrap_err();
+            assert_eq!(error.kind, kind, "{data:?}");
+            assert_eq!(error.position, parse::Position { line, column }, "{data:?}");
+        }
+    }
+
+    fn expected() -> impl Strategy<Value = Expected> {
+        // Any characters at all, including quotes, backslashes, line breaks and surrounding spaces.
+        let text = || prop::collection::vec(any::<char>(), 0..12).prop_map(String::from_iter);
+        let text = prop_oneof![text(), "[ \"\\\\\n\ra-z]{0,12}"];
+        (
+            text.clone(),
+            1..=25u8,
+            prop::option::of(text.clone()),
+            prop::option::of(text),
+        )
+            .prop_map(|(input, day, part1, part2)| Expected {
+                input,
+                day,
+                part1,
+                part2,
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn recorded_answers_read_back(expected in prop::collection::vec(expected(), 0..10)) {
+            let answers = Answers { expected };
+            prop_assert_eq!(answers.to_string().parse::<Answers>().unwrap(), answers);
+        }
+    }
+}
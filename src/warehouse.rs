@@ -1,85 +1,862 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap, fmt::Display, fs::File, io::Read, ops::RangeInclusive, path::PathBuf,
+    rc::Rc, str::FromStr,
+};
 
-#[derive(Debug, Clone)]
+use crate::{
+    parse::{self, Line, ParseError},
+    solution::Solution,
+};
+
+use crane::{Crane, CrateMover9000, CrateMover9001};
+
+pub mod crane;
+pub mod generate;
+pub mod plan;
+pub mod simulation;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Move {
     pub crates: usize,
+    /// 1-based position of the stack to take crates from, counting from the left.
     pub from: usize,
+    /// 1-based position of the stack to put crates on, counting from the left.
     pub to: usize,
 }
 
-#[derive(Clone, PartialEq, Eq)]
-pub enum Crane {
-    CrateMover9000,
-    CrateMover9001,
-}
+/// The name of a crate. Crates with the same name share it, so copying stacks never copies names.
+pub type Crate = Rc<str>;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Crates {
-    pub stacks: [Vec<char>; 9],
+    pub stacks: Vec<Vec<Crate>>,
+    /// The label of each stack, from the numbered footer of the drawing.
+    pub labels: Vec<String>,
     pub instructions: Vec<Move>,
 }
 
 impl Crates {
     pub fn load(file: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
-        let mut stacks: [Vec<char>; 9] = Default::default();
-        let mut instructions = Vec::new();
-        let data = std::fs::read_to_string(file.into())?;
-        let mut lines = data.lines().into_iter().peekable();
-
-        // Load stacks (until we hit the empty line)
-        while let Some(line) = lines.next().filter(|l| !l.is_empty()) {
-            line.chars()
-                .skip(1)
-                .step_by(4)
-                .enumerate()
-                .filter(|c| c.1.is_alphabetic())
-                .for_each(|(i, c)| stacks[i].push(c));
-        }
-
-        // The stacks were loaded the upside down, so we need to flip them.
-        stacks.iter_mut().for_each(|stack| stack.reverse());
-
-        lines.for_each(|line| {
-            let mut moves = line.split_whitespace().skip(1).step_by(2);
-            let instruction = Move {
-                crates: moves.next().and_then(|s| s.parse().ok()).unwrap(),
-                from: moves.next().and_then(|s| s.parse().ok()).unwrap(),
-                to: moves.next().and_then(|s| s.parse().ok()).unwrap(),
+        Self::from_reader(File::open(file.into())?)
+    }
+
+    pub fn from_reader(reader: impl Read) -> Result<Self, anyhow::Error> {
+        Ok(std::io::read_to_string(reader)?.parse()?)
+    }
+
+    /// Write the crates out in the same format they are loaded from, unless they couldn't be loaded
+    /// back, as [`Crates::validate`] checks.
+    pub fn save(&self, file: impl Into<PathBuf>) -> Result<(), anyhow::Error> {
+        self.validate()?;
+        Ok(std::fs::write(file.into(), self.to_string())?)
+    }
+
+    /// Check that the crates can be drawn in a way that parses back to the same crates: there is at
+    /// least one stack, each stack has a label, the labels are unique, labels and crate names are
+    /// single words without brackets, and every move is between stacks that exist.
+    pub fn validate(&self) -> Result<(), CratesError> {
+        let word = |text: &str| {
+            !text.is_empty() && !text.contains(|c: char| c.is_whitespace() || c == '[' || c == ']')
+        };
+        if self.stacks.is_empty() {
+            return Err(CratesError::Footer);
+        }
+        if self.labels.len() != self.stacks.len() {
+            return Err(CratesError::LabelCount);
+        }
+        for (i, label) in self.labels.iter().enumerate() {
+            if !word(label) {
+                return Err(CratesError::Footer);
+            }
+            if self.labels[..i].contains(label) {
+                return Err(CratesError::DuplicateLabel);
+            }
+        }
+        if !self.stacks.iter().flatten().all(|name| word(name)) {
+            return Err(CratesError::Crate);
+        }
+        let exists = |stack: usize| (1..=self.stacks.len()).contains(&stack);
+        if !self
+            .instructions
+            .iter()
+            .all(|inst| exists(inst.from) && exists(inst.to))
+        {
+            return Err(CratesError::UnknownStack);
+        }
+        Ok(())
+    }
+
+    /// The label of the stack at 1-based `position`, falling back to the position itself for
+    /// stacks without one.
+    fn label(&self, position: usize) -> String {
+        position
+            .checked_sub(1)
+            .and_then(|i| self.labels.get(i))
+            .cloned()
+            .unwrap_or_else(|| position.to_string())
+    }
+
+    pub fn execute_moves(&self, crane: impl Crane) -> Self {
+        let mut new_state = self.to_owned();
+        for inst in new_state.instructions.drain(..) {
+            move_crates(&mut new_state.stacks, &inst, &crane);
+        }
+        new_state
+    }
+
+    /// Like [`Crates::execute_moves`], but carries out the moves on these stacks rather than a copy,
+    /// without allocating for each move. The instructions are used up.
+    pub fn execute_moves_in_place(&mut self, crane: impl Crane) {
+        for inst in self.instructions.drain(..) {
+            move_crates_in_place(&mut self.stacks, &inst, &crane);
+        }
+    }
+
+    /// Like [`Crates::execute_moves`], but checks every move against the current state first,
+    /// handling illegal ones as `invalid` says instead of panicking.
+    pub fn try_execute_moves(
+        &self,
+        crane: impl Crane,
+        invalid: InvalidMoves,
+    ) -> Result<Self, MoveError> {
+        let mut new_state = self.to_owned();
+        for (index, mut inst) in new_state.instructions.drain(..).enumerate() {
+            if let Err(reason) = check_move(&new_state.stacks, &inst) {
+                match (invalid, reason) {
+                    (InvalidMoves::Reject, _) => {
+                        return Err(MoveError {
+                            index,
+                            inst,
+                            reason,
+                        })
+                    }
+                    (InvalidMoves::Clamp, IllegalMove::NotEnoughCrates { available, .. }) => {
+                        inst.crates = available
+                    }
+                    _ => continue,
+                }
+            }
+            move_crates_in_place(&mut new_state.stacks, &inst, &crane);
+        }
+        Ok(new_state)
+    }
+
+    /// Work backwards from these stacks, taken as the state after `crane` carried out the
+    /// instructions, to the stacks the instructions started from. The instructions are kept, so
+    /// executing the result gives these stacks back.
+    ///
+    /// Fails with the last move that couldn't have been carried out, if these stacks could never be
+    /// the result of the instructions.
+    pub fn undo_moves(&self, crane: impl Crane) -> Result<Self, MoveError> {
+        let mut initial = self.to_owned();
+        for (index, inst) in self.instructions.iter().enumerate().rev() {
+            let undo = Move {
+                crates: inst.crates,
+                from: inst.to,
+                to: inst.from,
             };
-            instructions.push(instruction);
+            check_move(&initial.stacks, &undo).map_err(|reason| MoveError {
+                index,
+                inst: inst.clone(),
+                reason,
+            })?;
+            if let Some(pile) = transfer(&mut initial.stacks, &undo) {
+                crane.unarrange(pile);
+            }
+        }
+        Ok(initial)
+    }
+
+    /// The crate on top of each stack, from left to right, skipping empty stacks.
+    pub fn topmost_crates(&self) -> Vec<&str> {
+        self.stacks
+            .iter()
+            .filter_map(|s| s.last().map(|c| &**c))
+            .collect()
+    }
+
+    /// The crates on top of the stacks, joined together.
+    pub fn topmost(&self) -> String {
+        self.topmost_crates().concat()
+    }
+}
+
+/// Carry out a single move, which must be legal.
+fn move_crates(stacks: &mut [Vec<Crate>], inst: &Move, crane: &impl Crane) {
+    // Putting crates back where they came from leaves the stack as it was, even for a crane that
+    // picks them up one by one.
+    if inst.from == inst.to {
+        return;
+    }
+    let stack = &mut stacks[inst.from - 1];
+    let mut moving_crates = stack.split_off(stack.len() - inst.crates);
+    crane.arrange(&mut moving_crates);
+    stacks[inst.to - 1].extend(moving_crates);
+}
+
+/// Carry out a single move, which must be legal, by draining the crates straight onto the other
+/// stack and arranging them there. Neither stack needs a new allocation unless it outgrows its
+/// capacity.
+fn move_crates_in_place(stacks: &mut [Vec<Crate>], inst: &Move, crane: &impl Crane) {
+    if let Some(pile) = transfer(stacks, inst) {
+        crane.arrange(pile);
+    }
+}
+
+/// Drain the crates of a legal move onto the other stack as they are, returning them in their new
+/// place, or `None` if the move puts them back where they came from.
+fn transfer<'a>(stacks: &'a mut [Vec<Crate>], inst: &Move) -> Option<&'a mut [Crate]> {
+    if inst.from == inst.to {
+        return None;
+    }
+    let (from, to) = if inst.from < inst.to {
+        let (left, right) = stacks.split_at_mut(inst.to - 1);
+        (&mut left[inst.from - 1], &mut right[0])
+    } else {
+        let (left, right) = stacks.split_at_mut(inst.from - 1);
+        (&mut right[0], &mut left[inst.to - 1])
+    };
+    let start = from.len() - inst.crates;
+    let bottom = to.len();
+    to.extend(from.drain(start..));
+    Some(&mut to[bottom..])
+}
+
+fn check_move(stacks: &[Vec<Crate>], inst: &Move) -> Result<(), IllegalMove> {
+    for stack in [inst.from, inst.to] {
+        if stack == 0 || stack > stacks.len() {
+            return Err(IllegalMove::NoSuchStack { stack });
+        }
+    }
+    let available = stacks[inst.from - 1].len();
+    if inst.crates > available {
+        return Err(IllegalMove::NotEnoughCrates {
+            requested: inst.crates,
+            available,
         });
+    }
+    Ok(())
+}
+
+/// How [`Crates::try_execute_moves`] handles a move that can't be carried out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidMoves {
+    /// Stop, and report the move.
+    #[default]
+    Reject,
+    /// Leave the stacks as they are, and carry on with the next move.
+    Skip,
+    /// Move as many crates as the stack holds. Moves to or from a missing stack are skipped.
+    Clamp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMove {
+    /// The 1-based stack position is zero or past the last stack.
+    NoSuchStack {
+        stack: usize,
+    },
+    NotEnoughCrates {
+        requested: usize,
+        available: usize,
+    },
+}
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IllegalMove::NoSuchStack { stack } => write!(f, "there is no stack {stack}"),
+            IllegalMove::NotEnoughCrates {
+                requested,
+                available,
+            } => write!(
+                f,
+                "can't take {requested} crates from a stack of {available}"
+            ),
+        }
+    }
+}
+
+/// A move that [`Crates::try_execute_moves`] rejected.
+#[derive(Debug, Clone)]
+pub struct MoveError {
+    /// Index of the move in [`Crates::instructions`].
+    pub index: usize,
+    pub inst: Move,
+    pub reason: IllegalMove,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Move { crates, from, to } = self.inst;
+        write!(
+            f,
+            "instruction {} (move {crates} from {from} to {to}): {}",
+            self.index, self.reason
+        )
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CratesError {
+    Footer,
+    /// There isn't exactly one label for each stack, which only happens for crates built by hand.
+    LabelCount,
+    DuplicateLabel,
+    Crate,
+    CrateOutsideStack,
+    CrateAcrossStacks,
+    Keyword(&'static str),
+    CrateCount,
+    UnknownStack,
+}
+
+impl Display for CratesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CratesError::Footer => write!(f, "expected a footer of stack labels below the drawing"),
+            CratesError::LabelCount => write!(f, "expected a label for every stack"),
+            CratesError::DuplicateLabel => write!(f, "expected every stack label to be unique"),
+            CratesError::Crate => write!(f, "expected a crate, like [A] or [AB]"),
+            CratesError::CrateOutsideStack => write!(f, "expected a crate above a stack label"),
+            CratesError::CrateAcrossStacks => {
+                write!(f, "expected a crate above a single stack label")
+            }
+            CratesError::Keyword(keyword) => write!(f, "expected `{keyword}`"),
+            CratesError::CrateCount => write!(f, "expected a number of crates"),
+            CratesError::UnknownStack => write!(f, "expected a stack label from the footer"),
+        }
+    }
+}
+
+impl std::error::Error for CratesError {}
+
+/// Push the crates drawn on `line` onto the stacks whose labels, spanning `columns`, are below them.
+/// Each name is only allocated the first time it is drawn, and shared through `names` after that.
+fn load_crates<'a>(
+    line: &Line<'a>,
+    columns: &[RangeInclusive<usize>],
+    stacks: &mut [Vec<Crate>],
+    names: &mut HashMap<&'a str, Crate>,
+) -> Result<(), ParseError<CratesError>> {
+    let mut chars = line.text.char_indices();
+    while let Some((offset, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let token = match line.text[offset..].find(']') {
+            Some(len) if c == '[' => &line.text[offset..=offset + len],
+            _ => {
+                let word = line.text[offset..]
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default();
+                return Err(line.error(word, CratesError::Crate));
+            }
+        };
+        // Skip over the rest of the crate
+        chars.nth(token.chars().count() - 2);
+
+        let name = &token[1..token.len() - 1];
+        if name.is_empty() || name.contains(|c: char| c == '[' || c.is_whitespace()) {
+            return Err(line.error(token, CratesError::Crate));
+        }
+        let start = line.position_of(token).column;
+        let end = start + token.chars().count() - 1;
+        let mut below = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| *column.start() <= end && start <= *column.end());
+        let stack = match (below.next(), below.next()) {
+            (Some((stack, _)), None) => stack,
+            (None, _) => return Err(line.error(token, CratesError::CrateOutsideStack)),
+            (Some(_), Some(_)) => return Err(line.error(token, CratesError::CrateAcrossStacks)),
+        };
+        let name = names.entry(name).or_insert_with(|| name.into());
+        stacks[stack].push(Rc::clone(name));
+    }
+    Ok(())
+}
+
+/// The 1-based position of the stack labelled by the next word.
+fn stack_number<'a>(
+    line: &Line<'a>,
+    words: &mut impl Iterator<Item = &'a str>,
+    labels: &[String],
+) -> Result<usize, ParseError<CratesError>> {
+    let label = line.expect_word(words, CratesError::UnknownStack)?;
+    labels
+        .iter()
+        .position(|l| l == label)
+        .map(|i| i + 1)
+        .ok_or_else(|| line.error(label, CratesError::UnknownStack))
+}
+
+impl FromStr for Crates {
+    type Err = ParseError<CratesError>;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut instructions = Vec::new();
+        let mut lines = parse::lines(data).peekable();
+
+        // Load the drawing (until we hit the empty line), which ends with a footer of stack labels
+        let mut drawing = Vec::new();
+        while let Some(line) = lines.next().filter(|l| !l.text.is_empty()) {
+            drawing.push(line);
+        }
+        let footer = drawing.pop().ok_or(ParseError {
+            position: parse::Position { line: 1, column: 1 },
+            found: String::new(),
+            kind: CratesError::Footer,
+        })?;
+
+        let mut labels = Vec::new();
+        let mut columns = Vec::new();
+        for label in footer.text.split_whitespace() {
+            if label.contains(['[', ']']) {
+                return Err(footer.error(label, CratesError::Footer));
+            }
+            if labels.iter().any(|l| l == label) {
+                return Err(footer.error(label, CratesError::DuplicateLabel));
+            }
+            let start = footer.position_of(label).column;
+            columns.push(start..=start + label.chars().count() - 1);
+            labels.push(label.to_owned());
+        }
+
+        // Read the drawing from the bottom up, so the stacks are loaded the right way up.
+        let mut stacks = vec![Vec::new(); labels.len()];
+        let mut names = HashMap::new();
+        for line in drawing.iter().rev() {
+            load_crates(line, &columns, &mut stacks, &mut names)?;
+        }
+
+        for line in lines {
+            let mut words = line.text.split_whitespace();
+            line.expect_keyword(&mut words, "move", CratesError::Keyword("move"))?;
+            let crates = line.expect_parsed(&mut words, CratesError::CrateCount)?;
+            line.expect_keyword(&mut words, "from", CratesError::Keyword("from"))?;
+            let from = stack_number(&line, &mut words, &labels)?;
+            line.expect_keyword(&mut words, "to", CratesError::Keyword("to"))?;
+            let to = stack_number(&line, &mut words, &labels)?;
+            instructions.push(Move { crates, from, to });
+        }
 
         Ok(Crates {
             stacks,
+            labels,
             instructions,
         })
     }
+}
 
-    pub fn execute_moves(&self, crane: Crane) -> Self {
-        let mut new_state = self.to_owned();
-        for inst in new_state.instructions.drain(..) {
-            let stack = &mut new_state.stacks[inst.from - 1];
-            let mut moving_crates = stack.split_off(stack.len() - inst.crates);
+/// Draws the stacks above their labels, followed by the moves, so that parsing the output gives
+/// back the same crates if [`Crates::validate`] accepts them. Each stack is drawn in a column as wide as its label or its widest crate,
+/// with the crates and label centred in it. Trailing spaces are left off.
+impl Display for Crates {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let labels: Vec<String> = (1..=self.stacks.len()).map(|i| self.label(i)).collect();
+        let widths: Vec<usize> = labels
+            .iter()
+            .zip(self.stacks.iter())
+            .map(|(label, stack)| {
+                let widest = stack.iter().map(|c| c.chars().count()).max().unwrap_or(1);
+                label.chars().count().max(widest + 2)
+            })
+            .collect();
 
-            // This crate picks up one by one, so the result will be flipped
-            if crane == Crane::CrateMover9000 {
-                moving_crates.reverse();
+        // Pad each cell out to its column width, then drop the padding at the end of the line.
+        let row = |cells: &mut dyn Iterator<Item = String>| {
+            let mut line = String::new();
+            for (cell, width) in cells.zip(widths.iter()) {
+                let left = (width - cell.chars().count()) / 2;
+                let right = width - cell.chars().count() - left;
+                line.push_str(&format!("{:left$}{cell}{:right$} ", "", ""));
             }
+            line.trim_end().to_owned()
+        };
 
-            for moving in moving_crates.drain(..) {
-                new_state.stacks[inst.to - 1].push(moving);
+        let height = self.stacks.iter().map(Vec::len).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let mut cells = self.stacks.iter().map(|stack| match stack.get(level) {
+                Some(name) => format!("[{name}]"),
+                None => String::new(),
+            });
+            writeln!(f, "{}", row(&mut cells))?;
+        }
+        writeln!(f, "{}", row(&mut labels.iter().cloned()))?;
+        writeln!(f)?;
+
+        for inst in self.instructions.iter() {
+            writeln!(
+                f,
+                "move {} from {} to {}",
+                inst.crates,
+                self.label(inst.from),
+                self.label(inst.to)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct SupplyStacks;
+
+impl Solution for SupplyStacks {
+    const DAY: u8 = 5;
+
+    type Input = Crates;
+    type Answer = String;
+
+    fn parse(input: &str) -> Result<Self::Input, anyhow::Error> {
+        Ok(input.parse()?)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        let state = input.try_execute_moves(CrateMover9000, InvalidMoves::Reject)?;
+        Ok(state.topmost())
+    }
+
+    fn part2(input: &Self::Input) -> Result<Self::Answer, anyhow::Error> {
+        let state = input.try_execute_moves(CrateMover9001, InvalidMoves::Reject)?;
+        Ok(state.topmost())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::warehouse::crane::{CapacityCrane, ChunkReversingCrane};
+
+    pub(super) const EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2
+";
+
+    /// Stacks with a single-letter crate for each letter of `layout`, from the bottom up.
+    pub(super) fn stacks(layout: &[&str]) -> Vec<Vec<Crate>> {
+        layout
+            .iter()
+            .map(|stack| stack.chars().map(|c| c.to_string().into()).collect())
+            .collect()
+    }
+
+    /// Random stacks of crates with labels up to three characters long, and moves that are all legal when applied in order.
+    fn crates() -> impl Strategy<Value = Crates> {
+        let name = "[A-Z0-9#.]{1,3}".prop_map(Crate::from);
+        let stacks = prop::collection::vec(prop::collection::vec(name, 0..8), 9);
+        let moves = prop::collection::vec(
+            any::<(
+                prop::sample::Index,
+                prop::sample::Index,
+                prop::sample::Index,
+            )>(),
+            0..30,
+        );
+        (stacks, moves).prop_map(|(stacks, moves)| {
+            let mut heights: Vec<usize> = stacks.iter().map(Vec::len).collect();
+            let mut instructions = Vec::new();
+            for (from, to, crates) in moves {
+                let occupied: Vec<usize> = (0..9).filter(|&i| heights[i] > 0).collect();
+                if occupied.is_empty() {
+                    break;
+                }
+                let from = *from.get(&occupied);
+                let to = to.index(9);
+                let crates = crates.index(heights[from]) + 1;
+                heights[from] -= crates;
+                heights[to] += crates;
+                instructions.push(Move {
+                    crates,
+                    from: from + 1,
+                    to: to + 1,
+                });
             }
+            Crates {
+                stacks,
+                labels: (1..=9).map(|i| i.to_string()).collect(),
+                instructions,
+            }
+        })
+    }
+
+    /// Crates that often can't be drawn: with no stacks, labels missing or repeated, labels and
+    /// crate names with spaces or brackets, or moves between stacks that don't exist.
+    fn any_crates() -> impl Strategy<Value = Crates> {
+        let name = prop_oneof![4 => "[A-Z0-9#.]{1,3}", 1 => "[A-Z \\[\\]]{0,3}"];
+        let stacks = prop::collection::vec(
+            prop::collection::vec(name.prop_map(Crate::from), 0..4),
+            0..5,
+        );
+        let label = prop_oneof![4 => "[0-9a-z]{1,2}", 1 => "[0-9a-z \\[\\]]{0,2}"];
+        let labels = prop::collection::vec(label, 0..6);
+        let moves = prop::collection::vec((1..4usize, 0..6usize, 0..6usize), 0..4);
+        (stacks, labels, moves).prop_map(|(stacks, labels, moves)| Crates {
+            stacks,
+            labels,
+            instructions: moves
+                .into_iter()
+                .map(|(crates, from, to)| Move { crates, from, to })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn example() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        assert_eq!(SupplyStacks::part1(&crates).unwrap(), "CMZ");
+        assert_eq!(SupplyStacks::part2(&crates).unwrap(), "MCD");
+    }
+
+    #[test]
+    fn invalid_move() {
+        let error = "[A]\n 1 \n\nmove 1 frm 1 to 1\n"
+            .parse::<Crates>()
+            .unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 4, column: 8 });
+        assert_eq!(error.kind, CratesError::Keyword("from"));
+    }
+
+    #[test]
+    fn stacks_from_footer() {
+        let data = "    [B]\n[A] [C] [D] [E]\n 10  20  30  x \n\nmove 1 from 20 to x\n";
+        let crates: Crates = data.parse().unwrap();
+        assert_eq!(crates.labels, ["10", "20", "30", "x"]);
+        assert_eq!(crates.stacks, stacks(&["A", "CB", "D", "E"]));
+        assert_eq!(crates.instructions[0].from, 2);
+        assert_eq!(crates.instructions[0].to, 4);
+        assert_eq!(SupplyStacks::part1(&crates).unwrap(), "ACDB");
+    }
+
+    #[test]
+    fn multi_character_crates() {
+        let data = "     [42]\n[AB] [x.]  [7]\n  1    2    3\n\nmove 2 from 2 to 1\n";
+        let crates: Crates = data.parse().unwrap();
+        let names = |stack: &[Crate]| stack.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let stacks: Vec<_> = crates.stacks.iter().map(|stack| names(stack)).collect();
+        assert_eq!(stacks, [vec!["AB"], vec!["x.", "42"], vec!["7"]]);
+        assert_eq!(crates.topmost_crates(), ["AB", "42", "7"]);
+
+        let state = crates.execute_moves(CrateMover9001);
+        assert_eq!(state.topmost_crates(), ["42", "7"]);
+        assert_eq!(state.topmost(), "427");
+
+        let error = "[A] [LONG]\n 1   2  3\n\n".parse::<Crates>().unwrap_err();
+        assert_eq!(error.kind, CratesError::CrateAcrossStacks);
+        let error = "[] [A]\n 1   2\n\n".parse::<Crates>().unwrap_err();
+        assert_eq!(error.kind, CratesError::Crate);
+    }
+
+    #[test]
+    fn crates_share_names() {
+        let crates: Crates = "[AB]\n[AB] [C]\n 1    2\n\n".parse().unwrap();
+        assert!(Rc::ptr_eq(&crates.stacks[0][0], &crates.stacks[0][1]));
+        let copy = crates.clone();
+        assert!(Rc::ptr_eq(&copy.stacks[1][0], &crates.stacks[1][0]));
+    }
+
+    #[test]
+    fn invalid_drawing() {
+        let error = "[A] [B]\n 1\n\n".parse::<Crates>().unwrap_err();
+        assert_eq!(error.position, parse::Position { line: 1, column: 5 });
+        assert_eq!(error.kind, CratesError::CrateOutsideStack);
+
+        let error = "[A]\n 1 \n\nmove 1 from 1 to 2\n"
+            .parse::<Crates>()
+            .unwrap_err();
+        assert_eq!(error.kind, CratesError::UnknownStack);
+
+        let error = "[A]\n[B]\n\n".parse::<Crates>().unwrap_err();
+        assert_eq!(error.kind, CratesError::Footer);
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let data = "[A]\n[B] [C]\n 1   2\n\nmove 1 from 2 to 1\nmove 4 from 1 to 2\n";
+        let crates: Crates = data.parse().unwrap();
+        let execute = |invalid| crates.try_execute_moves(CrateMover9000, invalid);
+
+        let error = execute(InvalidMoves::Reject).unwrap_err();
+        assert_eq!(error.index, 1);
+        let reason = IllegalMove::NotEnoughCrates {
+            requested: 4,
+            available: 3,
+        };
+        assert_eq!(error.reason, reason);
+
+        let skipped = execute(InvalidMoves::Skip).unwrap();
+        assert_eq!(skipped.stacks, stacks(&["BAC", ""]));
+
+        let clamped = execute(InvalidMoves::Clamp).unwrap();
+        assert_eq!(clamped.stacks, stacks(&["", "CAB"]));
+
+        // Labels are checked when parsing, but moves can be built by hand.
+        let mut crates = crates.clone();
+        crates.instructions[1] = Move {
+            crates: 1,
+            from: 0,
+            to: 1,
+        };
+        let error = crates
+            .try_execute_moves(CrateMover9001, InvalidMoves::Reject)
+            .unwrap_err();
+        assert_eq!(error.reason, IllegalMove::NoSuchStack { stack: 0 });
+    }
+
+    #[test]
+    fn render_drawing() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        let trimmed: String = EXAMPLE
+            .lines()
+            .map(|l| l.trim_end().to_owned() + "\n")
+            .collect();
+        assert_eq!(crates.to_string(), trimmed);
+
+        // Wide labels widen their columns, with the crates centred above them.
+        let data = "     [B]\n[A]  [D]  [C]\n10  stack  x\n\nmove 1 from stack to 10\n";
+        let crates: Crates = data.parse().unwrap();
+        assert_eq!(crates.stacks, stacks(&["A", "DB", "C"]));
+        assert_eq!(crates.to_string(), data);
+    }
+
+    #[test]
+    fn unrepresentable_crates() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        assert_eq!(crates.validate(), Ok(()));
+        let changed = |change: &dyn Fn(&mut Crates)| {
+            let mut invalid = crates.clone();
+            change(&mut invalid);
+            invalid
+        };
+        let cases = [
+            (changed(&|c| c.stacks.clear()), CratesError::Footer),
+            (changed(&|c| c.labels.truncate(2)), CratesError::LabelCount),
+            (
+                changed(&|c| c.labels[1] = "3".into()),
+                CratesError::DuplicateLabel,
+            ),
+            (
+                changed(&|c| c.labels[0] = "a b".into()),
+                CratesError::Footer,
+            ),
+            (
+                changed(&|c| c.labels[0] = "[1]".into()),
+                CratesError::Footer,
+            ),
+            (
+                changed(&|c| c.stacks[0].push("A B".into())),
+                CratesError::Crate,
+            ),
+            (
+                changed(&|c| c.stacks[2].push("]".into())),
+                CratesError::Crate,
+            ),
+            (
+                changed(&|c| c.instructions[0].to = 4),
+                CratesError::UnknownStack,
+            ),
+        ];
+        for (invalid, kind) in cases {
+            assert_eq!(invalid.validate(), Err(kind));
+            assert_ne!(invalid.to_string().parse::<Crates>().ok(), Some(invalid));
         }
-        new_state
     }
 
-    pub fn topmost(&self) -> String {
-        let mut top = String::new();
-        for s in self.stacks.iter() {
-            if let Some(c) = s.last() {
-                top.push(*c)
+    #[test]
+    fn undo_example() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001] {
+            let mut state = crates.execute_moves(crane);
+            state.instructions = crates.instructions.clone();
+            assert_eq!(state.undo_moves(crane).unwrap(), crates);
+        }
+
+        // The last move put a crate on the second stack, so it can't have ended up empty.
+        let mut state = crates.clone();
+        state.stacks = stacks(&["CMZ", "", "NDP"]);
+        let error = state.undo_moves(CrateMover9000).unwrap_err();
+        assert_eq!(error.index, 3);
+        let reason = IllegalMove::NotEnoughCrates {
+            requested: 1,
+            available: 0,
+        };
+        assert_eq!(error.reason, reason);
+    }
+
+    proptest! {
+        #[test]
+        fn undo_recovers_initial_stacks(crates in crates(), size in 1..10usize) {
+            let cranes: [Box<dyn Crane>; 4] = [
+                Box::new(CrateMover9000),
+                Box::new(CrateMover9001),
+                Box::new(CapacityCrane { capacity: size }),
+                Box::new(ChunkReversingCrane { chunk: size }),
+            ];
+            for crane in cranes {
+                let mut state = crates.execute_moves(&crane);
+                state.instructions = crates.instructions.clone();
+                prop_assert_eq!(state.undo_moves(&crane).unwrap(), crates.clone());
+            }
+        }
+
+        #[test]
+        fn render_round_trips(crates in crates()) {
+            prop_assert_eq!(crates.validate(), Ok(()));
+            let rendered = crates.to_string();
+            prop_assert_eq!(rendered.parse::<Crates>().unwrap(), crates);
+        }
+
+        #[test]
+        fn valid_crates_are_the_ones_that_round_trip(crates in any_crates()) {
+            let read_back = crates.to_string().parse::<Crates>().ok();
+            prop_assert_eq!(crates.validate().is_ok(), read_back.as_ref() == Some(&crates));
+        }
+
+        #[test]
+        fn in_place_matches_copying(crates in crates(), size in 1..10usize) {
+            let cranes: [Box<dyn Crane>; 4] = [
+                Box::new(CrateMover9000),
+                Box::new(CrateMover9001),
+                Box::new(CapacityCrane { capacity: size }),
+                Box::new(ChunkReversingCrane { chunk: size }),
+            ];
+            for crane in cranes {
+                let mut in_place = crates.clone();
+                in_place.execute_moves_in_place(&crane);
+                prop_assert_eq!(in_place, crates.execute_moves(&crane));
+            }
+        }
+
+        #[test]
+        fn moves_match_single_crate_steps(crates in crates()) {
+            let mut cm9000 = crates.stacks.clone();
+            let mut cm9001 = crates.stacks.clone();
+            for inst in crates.instructions.iter() {
+                for _ in 0..inst.crates {
+                    let moving = cm9000[inst.from - 1].pop().unwrap();
+                    cm9000[inst.to - 1].push(moving);
+                }
+                // Moving a pile at once is the same as moving it via a temporary stack twice.
+                let mut pile = Vec::new();
+                for _ in 0..inst.crates {
+                    pile.push(cm9001[inst.from - 1].pop().unwrap());
+                }
+                while let Some(moving) = pile.pop() {
+                    cm9001[inst.to - 1].push(moving);
+                }
             }
+            prop_assert_eq!(crates.execute_moves(CrateMover9000).stacks, cm9000);
+            prop_assert_eq!(crates.execute_moves(CrateMover9001).stacks, cm9001);
         }
-        top
     }
 }
@@ -0,0 +1,319 @@
+use anyhow::anyhow;
+
+use super::Crate;
+
+/// A model of crane, which decides what order a pile of crates ends up in when it is moved.
+///
+/// The order only depends on the size of the pile, never on the crates in it, so it can always be
+/// undone.
+pub trait Crane {
+    /// The name of the model, which [`model`] turns back into the crane.
+    fn name(&self) -> String;
+
+    /// The order a pile of `len` crates is put down in: the `i`th crate put down, from the bottom
+    /// up, is the `order(len)[i]`th crate lifted, also from the bottom up. Every position below
+    /// `len` appears exactly once.
+    fn order(&self, len: usize) -> Vec<usize>;
+
+    /// Rearrange `pile`, the crates lifted off a stack from the bottom up, into the order they are
+    /// put down on the other stack, as [`Crane::order`] says.
+    ///
+    /// Cranes may replace this with a quicker way of doing the same thing.
+    fn arrange(&self, pile: &mut [Crate]) {
+        let lifted = pile.to_vec();
+        for (put, &from) in pile.iter_mut().zip(self.order(lifted.len()).iter()) {
+            *put = lifted[from].clone();
+        }
+    }
+
+    /// Undo [`Crane::arrange`], putting a pile that was put down back in the order it was lifted.
+    fn unarrange(&self, pile: &mut [Crate]) {
+        let put = pile.to_vec();
+        for (name, from) in put.into_iter().zip(self.order(pile.len())) {
+            pile[from] = name;
+        }
+    }
+}
+
+impl<C: Crane + ?Sized> Crane for &C {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn order(&self, len: usize) -> Vec<usize> {
+        (**self).order(len)
+    }
+
+    fn arrange(&self, pile: &mut [Crate]) {
+        (**self).arrange(pile)
+    }
+
+    fn unarrange(&self, pile: &mut [Crate]) {
+        (**self).unarrange(pile)
+    }
+}
+
+impl<C: Crane + ?Sized> Crane for Box<C> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn order(&self, len: usize) -> Vec<usize> {
+        (**self).order(len)
+    }
+
+    fn arrange(&self, pile: &mut [Crate]) {
+        (**self).arrange(pile)
+    }
+
+    fn unarrange(&self, pile: &mut [Crate]) {
+        (**self).unarrange(pile)
+    }
+}
+
+/// Picks crates up one at a time, so the pile ends up upside down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover9000".into()
+    }
+
+    fn order(&self, len: usize) -> Vec<usize> {
+        (0..len).rev().collect()
+    }
+
+    fn arrange(&self, pile: &mut [Crate]) {
+        pile.reverse();
+    }
+
+    fn unarrange(&self, pile: &mut [Crate]) {
+        pile.reverse();
+    }
+}
+
+/// Picks the whole pile up at once, so it stays the right way up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover9001".into()
+    }
+
+    fn order(&self, len: usize) -> Vec<usize> {
+        (0..len).collect()
+    }
+
+    fn arrange(&self, _pile: &mut [Crate]) {}
+
+    fn unarrange(&self, _pile: &mut [Crate]) {}
+}
+
+/// Lifts at most `capacity` crates at a time, keeping each lift the right way up, so a bigger pile
+/// is split into several lifts taken from the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityCrane {
+    pub capacity: usize,
+}
+
+impl CapacityCrane {
+    fn lift<T>(&self, pile: &mut [T]) {
+        // Turning the pile over puts the lifts in order, starting from the top, but each lift is
+        // upside down.
+        pile.reverse();
+        for lift in pile.chunks_mut(self.capacity.max(1)) {
+            lift.reverse();
+        }
+    }
+}
+
+impl Crane for CapacityCrane {
+    fn name(&self) -> String {
+        format!("capacity:{}", self.capacity)
+    }
+
+    fn order(&self, len: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..len).collect();
+        self.lift(&mut order);
+        order
+    }
+
+    fn arrange(&self, pile: &mut [Crate]) {
+        self.lift(pile);
+    }
+}
+
+/// Turns over every group of `chunk` crates, counting from the top of the pile, but puts the groups
+/// down in the order they were stacked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkReversingCrane {
+    pub chunk: usize,
+}
+
+impl ChunkReversingCrane {
+    fn turn<T>(&self, pile: &mut [T]) {
+        for group in pile.rchunks_mut(self.chunk.max(1)) {
+            group.reverse();
+        }
+    }
+}
+
+impl Crane for ChunkReversingCrane {
+    fn name(&self) -> String {
+        format!("chunks:{}", self.chunk)
+    }
+
+    fn order(&self, len: usize) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..len).collect();
+        self.turn(&mut order);
+        order
+    }
+
+    fn arrange(&self, pile: &mut [Crate]) {
+        self.turn(pile);
+    }
+
+    // Turning every group over twice puts it back the way it was.
+    fn unarrange(&self, pile: &mut [Crate]) {
+        self.turn(pile);
+    }
+}
+
+/// The crane model called `name`: `CrateMover9000` (or `9000`), `CrateMover9001` (or `9001`),
+/// `capacity:<n>` or `chunks:<n>`.
+pub fn model(name: &str) -> Result<Box<dyn Crane>, anyhow::Error> {
+    let size = |value: &str| match value.parse() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(anyhow!(
+            "Invalid crane size in {name}: expected a positive number"
+        )),
+    };
+    match name.split_once(':') {
+        None if name == "CrateMover9000" || name == "9000" => Ok(Box::new(CrateMover9000)),
+        None if name == "CrateMover9001" || name == "9001" => Ok(Box::new(CrateMover9001)),
+        Some(("capacity", capacity)) => Ok(Box::new(CapacityCrane {
+            capacity: size(capacity)?,
+        })),
+        Some(("chunks", chunk)) => Ok(Box::new(ChunkReversingCrane {
+            chunk: size(chunk)?,
+        })),
+        _ => Err(anyhow!("Unknown crane model: {name}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn arranged(crane: &dyn Crane, pile: &str) -> String {
+        let mut pile: Vec<Crate> = pile.chars().map(|c| c.to_string().into()).collect();
+        crane.arrange(&mut pile);
+        pile.concat()
+    }
+
+    #[test]
+    fn arrangements() {
+        assert_eq!(arranged(&CrateMover9000, "ABCDE"), "EDCBA");
+        assert_eq!(arranged(&CrateMover9001, "ABCDE"), "ABCDE");
+        assert_eq!(arranged(&CapacityCrane { capacity: 2 }, "ABCDE"), "DEBCA");
+        assert_eq!(
+            arranged(&ChunkReversingCrane { chunk: 2 }, "ABCDE"),
+            "ACBED"
+        );
+    }
+
+    #[test]
+    fn rotating() {
+        assert_eq!(arranged(&Rotating, "ABCDE"), "EABCD");
+        assert_eq!(arranged(&Rotating, ""), "");
+    }
+
+    #[test]
+    fn models_by_name() {
+        for name in ["CrateMover9000", "CrateMover9001", "capacity:3", "chunks:2"] {
+            assert_eq!(model(name).unwrap().name(), name);
+        }
+        assert_eq!(model("9000").unwrap().name(), "CrateMover9000");
+        assert!(model("capacity:0").is_err());
+        assert!(model("chunks").is_err());
+        assert!(model("CrateMover9002").is_err());
+    }
+
+    /// Moves the top crate of the pile to the bottom, relying on the default
+    /// [`Crane::arrange`] and [`Crane::unarrange`].
+    struct Rotating;
+
+    impl Crane for Rotating {
+        fn name(&self) -> String {
+            "rotating".into()
+        }
+
+        fn order(&self, len: usize) -> Vec<usize> {
+            let mut order: Vec<usize> = (0..len).collect();
+            order.rotate_right(1.min(len));
+            order
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn unarrange_undoes_arrange(pile in "[A-Z]{0,12}", size in 1..15usize) {
+            let cranes: [&dyn Crane; 5] = [
+                &CrateMover9000,
+                &CrateMover9001,
+                &CapacityCrane { capacity: size },
+                &ChunkReversingCrane { chunk: size },
+                &Rotating,
+            ];
+            for crane in cranes {
+                let mut crates: Vec<Crate> = pile.chars().map(|c| c.to_string().into()).collect();
+                crane.arrange(&mut crates);
+                crane.unarrange(&mut crates);
+                prop_assert_eq!(&crates.concat(), &pile);
+            }
+        }
+
+        #[test]
+        fn arrange_follows_order(len in 0..15usize, size in 1..15usize) {
+            let cranes: [&dyn Crane; 5] = [
+                &CrateMover9000,
+                &CrateMover9001,
+                &CapacityCrane { capacity: size },
+                &ChunkReversingCrane { chunk: size },
+                &Rotating,
+            ];
+            for crane in cranes {
+                let order = crane.order(len);
+                let mut sorted = order.clone();
+                sorted.sort_unstable();
+                prop_assert!(sorted.into_iter().eq(0..len));
+
+                let mut pile: Vec<Crate> = (0..len).map(|i| i.to_string().into()).collect();
+                crane.arrange(&mut pile);
+                let arranged: Vec<usize> = pile.iter().map(|c| c.parse().unwrap()).collect();
+                prop_assert_eq!(arranged, order);
+            }
+        }
+
+
+        #[test]
+        fn sized_cranes_match_the_originals(pile in "[A-Z]{0,12}", size in 1..15usize) {
+            let len = pile.len();
+            // Lifting a crate at a time, or turning over the whole pile at once, is a CrateMover9000.
+            let reversed = arranged(&CrateMover9000, &pile);
+            let lifted = arranged(&CapacityCrane { capacity: 1 }, &pile);
+            let turned = arranged(&ChunkReversingCrane { chunk: len.max(size) }, &pile);
+            prop_assert_eq!(&lifted, &reversed);
+            prop_assert_eq!(&turned, &reversed);
+            // Lifting the whole pile, or turning over single crates, is a CrateMover9001.
+            let lifted = arranged(&CapacityCrane { capacity: len.max(size) }, &pile);
+            let turned = arranged(&ChunkReversingCrane { chunk: 1 }, &pile);
+            prop_assert_eq!(&lifted, &pile);
+            prop_assert_eq!(&turned, &pile);
+        }
+    }
+}
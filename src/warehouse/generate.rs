@@ -0,0 +1,186 @@
+use super::{Crate, Crates, Move};
+use crate::rng::SplitMix64;
+
+/// How tall the stacks are drawn before any moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heights {
+    /// Every stack has this many crates.
+    Fixed(usize),
+    /// Each stack has between `min` and `max` crates, inclusive, all equally likely.
+    Uniform { min: usize, max: usize },
+    /// Stacks get taller towards the middle, up to `peak` crates, with one crate at either end.
+    Pyramid { peak: usize },
+}
+
+/// Generates random crate scenarios, with a drawing of the stacks and moves that are all legal
+/// when carried out in order. The same settings always generate the same scenario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generator {
+    pub seed: u64,
+    pub stacks: usize,
+    pub heights: Heights,
+    /// The number of moves, unless every stack is empty before then.
+    pub moves: usize,
+    /// The most crates a single move takes, or `None` to allow a whole stack.
+    pub max_lift: Option<usize>,
+}
+
+impl Generator {
+    /// A generator for scenarios the size of a puzzle input.
+    pub fn new(seed: u64) -> Self {
+        Generator {
+            seed,
+            stacks: 9,
+            heights: Heights::Uniform { min: 1, max: 8 },
+            moves: 500,
+            max_lift: None,
+        }
+    }
+
+    pub fn with_stacks(mut self, stacks: usize) -> Self {
+        self.stacks = stacks;
+        self
+    }
+
+    pub fn with_heights(mut self, heights: Heights) -> Self {
+        self.heights = heights;
+        self
+    }
+
+    pub fn with_moves(mut self, moves: usize) -> Self {
+        self.moves = moves;
+        self
+    }
+
+    pub fn with_max_lift(mut self, max_lift: usize) -> Self {
+        self.max_lift = Some(max_lift);
+        self
+    }
+
+    pub fn generate(&self) -> Crates {
+        let mut rng = SplitMix64(self.seed);
+
+        // Every crate with the same letter shares its name.
+        let letters: Vec<Crate> = ('A'..='Z').map(|c| c.to_string().into()).collect();
+        let stacks: Vec<Vec<Crate>> = (0..self.stacks)
+            .map(|i| {
+                let height = match self.heights {
+                    Heights::Fixed(height) => height,
+                    Heights::Uniform { min, max } => min + rng.below(max.saturating_sub(min) + 1),
+                    Heights::Pyramid { peak } => {
+                        let from_edge = i.min(self.stacks - 1 - i);
+                        (1 + from_edge).min(peak)
+                    }
+                };
+                (0..height)
+                    .map(|_| letters[rng.below(26)].clone())
+                    .collect()
+            })
+            .collect();
+
+        // Only the heights matter for keeping the moves legal.
+        let mut heights: Vec<usize> = stacks.iter().map(Vec::len).collect();
+        let mut occupied: Vec<usize> = (0..self.stacks).filter(|&i| heights[i] > 0).collect();
+        let mut instructions = Vec::with_capacity(self.moves);
+        for _ in 0..self.moves {
+            if occupied.is_empty() {
+                break;
+            }
+            let from = occupied[rng.below(occupied.len())];
+            // Avoid moves that put the crates back where they came from, if there's anywhere else.
+            let to = match rng.below(self.stacks.max(2) - 1) {
+                to if to >= from => (to + 1).min(self.stacks - 1),
+                to => to,
+            };
+            let most = self
+                .max_lift
+                .map_or(heights[from], |max| max.clamp(1, heights[from]));
+            let crates = 1 + rng.below(most);
+
+            heights[from] -= crates;
+            heights[to] += crates;
+            if heights[from] == 0 {
+                occupied.retain(|&i| i != from);
+            }
+            if heights[to] == crates && to != from {
+                occupied.push(to);
+            }
+            instructions.push(Move {
+                crates,
+                from: from + 1,
+                to: to + 1,
+            });
+        }
+
+        Crates {
+            stacks,
+            labels: (1..=self.stacks).map(|i| i.to_string()).collect(),
+            instructions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::warehouse::{crane::CrateMover9000, InvalidMoves};
+
+    #[test]
+    fn same_seed_same_scenario() {
+        let generator = Generator::new(7);
+        assert_eq!(generator.generate(), generator.generate());
+        assert_ne!(generator.generate(), Generator::new(8).generate());
+    }
+
+    #[test]
+    fn heights() {
+        let generator = Generator::new(1).with_stacks(5).with_moves(0);
+        let crates = generator.clone().with_heights(Heights::Fixed(3)).generate();
+        assert!(crates.stacks.iter().all(|stack| stack.len() == 3));
+
+        let pyramid = Heights::Pyramid { peak: 2 };
+        let crates = generator.with_heights(pyramid).generate();
+        let heights: Vec<usize> = crates.stacks.iter().map(Vec::len).collect();
+        assert_eq!(heights, [1, 2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let crates = Generator::new(3).with_moves(50).generate();
+        let file = std::env::temp_dir().join(format!("aoc22-{}-crates.txt", std::process::id()));
+        crates.save(&file).unwrap();
+        assert_eq!(Crates::load(&file).unwrap(), crates);
+        std::fs::remove_file(file).unwrap();
+    }
+
+    proptest! {
+        #[test]
+        fn scenarios_parse_and_are_legal(
+            seed in any::<u64>(),
+            stacks in 1..12usize,
+            (min, max) in (0..10usize, 0..10usize),
+            moves in 0..200usize,
+            max_lift in 1..5usize,
+        ) {
+            let heights = Heights::Uniform { min: min.min(max), max: min.max(max) };
+            let generator = Generator::new(seed)
+                .with_stacks(stacks)
+                .with_heights(heights)
+                .with_moves(moves);
+            for generator in [generator.clone(), generator.with_max_lift(max_lift)] {
+                let crates = generator.generate();
+                prop_assert_eq!(crates.stacks.len(), stacks);
+                for stack in crates.stacks.iter() {
+                    prop_assert!((min.min(max)..=min.max(max)).contains(&stack.len()));
+                }
+                if let Some(max_lift) = generator.max_lift {
+                    prop_assert!(crates.instructions.iter().all(|inst| inst.crates <= max_lift));
+                }
+                prop_assert!(crates.try_execute_moves(CrateMover9000, InvalidMoves::Reject).is_ok());
+                prop_assert_eq!(crates.to_string().parse::<Crates>().unwrap(), crates);
+            }
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fmt::Display,
+};
+
+use super::{crane::Crane, move_crates, Crate, Crates, Move};
+
+/// Number of arrangements a [`Planner`] examines before giving up, unless told otherwise.
+pub const DEFAULT_BUDGET: usize = 100_000;
+
+/// What a plan should achieve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// The crates on top of the stacks, read from left to right, as [`Crates::topmost_crates`]
+    /// gives them.
+    Topmost(Vec<Crate>),
+    /// Every stack, from the bottom up.
+    Layout(Vec<Vec<Crate>>),
+}
+
+impl Target {
+    fn reached(&self, stacks: &[Vec<Crate>]) -> bool {
+        match self {
+            Target::Topmost(top) => top.iter().eq(stacks.iter().filter_map(|s| s.last())),
+            Target::Layout(layout) => layout == stacks,
+        }
+    }
+
+    /// A lower bound on the number of moves needed to reach the target from `stacks`.
+    fn estimate(&self, stacks: &[Vec<Crate>]) -> usize {
+        match self {
+            // Every stack whose top is wrong has to be moved from or to, and each move touches two
+            // stacks. This only holds if no stack may be left empty.
+            Target::Topmost(top) if top.len() == stacks.len() => {
+                let wrong = top
+                    .iter()
+                    .zip(stacks)
+                    .filter(|(c, stack)| stack.last() != Some(c))
+                    .count();
+                wrong.div_ceil(2)
+            }
+            Target::Topmost(_) => 0,
+            // Every stack with crates on top of its finished part must be moved from at least
+            // once, and every stack still missing crates must be moved to at least once.
+            Target::Layout(layout) => {
+                let (mut surplus, mut missing) = (0, 0);
+                for (stack, target) in stacks.iter().zip(layout) {
+                    let done = stack.iter().zip(target).take_while(|(a, b)| a == b).count();
+                    surplus += usize::from(done < stack.len());
+                    missing += usize::from(done < target.len());
+                }
+                surplus.max(missing)
+            }
+        }
+    }
+
+    /// Whether the crates in `stacks` could ever be rearranged to match the target.
+    fn possible(&self, stacks: &[Vec<Crate>]) -> bool {
+        fn count<'a>(crates: impl Iterator<Item = &'a Crate>) -> HashMap<&'a str, usize> {
+            let mut counts = HashMap::new();
+            for c in crates {
+                *counts.entry(&**c).or_default() += 1;
+            }
+            counts
+        }
+        let available = count(stacks.iter().flatten());
+        match self {
+            Target::Topmost(top) => {
+                top.len() <= stacks.len()
+                    && count(top.iter())
+                        .iter()
+                        .all(|(c, n)| available.get(c).is_some_and(|a| a >= n))
+            }
+            Target::Layout(layout) => {
+                layout.len() == stacks.len() && count(layout.iter().flatten()) == available
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanError {
+    /// No sequence of moves reaches the target.
+    Unreachable,
+    /// The search examined `explored` arrangements without finding the target.
+    BudgetExhausted { explored: usize },
+}
+
+impl Display for PlanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanError::Unreachable => write!(f, "no sequence of moves reaches the target"),
+            PlanError::BudgetExhausted { explored } => write!(
+                f,
+                "gave up after examining {explored} arrangements without reaching the target"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+/// A shortest sequence of moves reaching a [`Target`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    pub moves: Vec<Move>,
+    /// Number of arrangements examined while searching.
+    pub explored: usize,
+}
+
+/// Searches for the fewest moves that rearrange the crates into a [`Target`] with a given crane,
+/// using A*.
+#[derive(Debug, Clone)]
+pub struct Planner<C> {
+    pub crane: C,
+    /// The most arrangements to examine before giving up.
+    pub budget: usize,
+}
+
+/// An arrangement found during the search, and the best known way of reaching it.
+struct Node {
+    stacks: Vec<Vec<Crate>>,
+    cost: usize,
+    previous: Option<(usize, Move)>,
+}
+
+impl<C: Crane> Planner<C> {
+    pub fn new(crane: C) -> Self {
+        Planner {
+            crane,
+            budget: DEFAULT_BUDGET,
+        }
+    }
+
+    pub fn with_budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Plan how to get from the stacks of `start` to `target`. The instructions of `start` are
+    /// ignored.
+    pub fn plan(&self, start: &Crates, target: &Target) -> Result<Plan, PlanError> {
+        if !target.possible(&start.stacks) {
+            return Err(PlanError::Unreachable);
+        }
+
+        let mut nodes = vec![Node {
+            stacks: start.stacks.clone(),
+            cost: 0,
+            previous: None,
+        }];
+        let mut seen = HashMap::from([(start.stacks.clone(), 0)]);
+        let mut queue = BinaryHeap::from([Reverse((target.estimate(&start.stacks), 0, 0))]);
+        let mut explored = 0;
+
+        while let Some(Reverse((_, cost, id))) = queue.pop() {
+            // A cheaper way here was found after this entry was queued.
+            if cost > nodes[id].cost {
+                continue;
+            }
+            if target.reached(&nodes[id].stacks) {
+                return Ok(Plan {
+                    moves: path(&nodes, id),
+                    explored,
+                });
+            }
+            if explored == self.budget {
+                return Err(PlanError::BudgetExhausted { explored });
+            }
+            explored += 1;
+
+            let current = nodes[id].stacks.clone();
+            for inst in moves(&current) {
+                let mut stacks = current.clone();
+                move_crates(&mut stacks, &inst, &self.crane);
+                let cost = cost + 1;
+                let next = match seen.get(&stacks) {
+                    Some(&next) if nodes[next].cost <= cost => continue,
+                    Some(&next) => {
+                        nodes[next].cost = cost;
+                        nodes[next].previous = Some((id, inst));
+                        next
+                    }
+                    None => {
+                        seen.insert(stacks.clone(), nodes.len());
+                        nodes.push(Node {
+                            stacks,
+                            cost,
+                            previous: Some((id, inst)),
+                        });
+                        nodes.len() - 1
+                    }
+                };
+                let estimate = cost + target.estimate(&nodes[next].stacks);
+                queue.push(Reverse((estimate, cost, next)));
+            }
+        }
+        Err(PlanError::Unreachable)
+    }
+}
+
+/// Every legal move from `stacks`.
+fn moves(stacks: &[Vec<Crate>]) -> impl Iterator<Item = Move> + '_ {
+    (0..stacks.len()).flat_map(move |from| {
+        (0..stacks.len())
+            .filter(move |&to| to != from)
+            .flat_map(move |to| {
+                (1..=stacks[from].len()).map(move |crates| Move {
+                    crates,
+                    from: from + 1,
+                    to: to + 1,
+                })
+            })
+    })
+}
+
+/// The moves leading from the start to node `id`.
+fn path(nodes: &[Node], mut id: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some((previous, inst)) = &nodes[id].previous {
+        moves.push(inst.clone());
+        id = *previous;
+    }
+    moves.reverse();
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::warehouse::{
+        crane::{CrateMover9000, CrateMover9001},
+        tests::{stacks, EXAMPLE},
+    };
+
+    fn topmost(crates: &str) -> Vec<Crate> {
+        crates.chars().map(|c| c.to_string().into()).collect()
+    }
+
+    fn execute(start: &Crates, plan: &Plan, crane: impl Crane) -> Crates {
+        let mut crates = start.clone();
+        crates.instructions = plan.moves.clone();
+        crates.execute_moves(crane)
+    }
+
+    #[test]
+    fn plan_topmost() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        for target in ["CMZ", "NDP", "MCD"] {
+            let planner = Planner::new(CrateMover9000);
+            let target = Target::Topmost(topmost(target));
+            let plan = planner.plan(&crates, &target).unwrap();
+            assert!(target.reached(&execute(&crates, &plan, CrateMover9000).stacks));
+        }
+        let target = Target::Topmost(topmost("DCP"));
+        let plan = Planner::new(CrateMover9001).plan(&crates, &target).unwrap();
+        assert_eq!(
+            plan.moves,
+            [Move {
+                crates: 1,
+                from: 2,
+                to: 1
+            }]
+        );
+
+        let plan = Planner::new(CrateMover9001)
+            .plan(&crates, &Target::Topmost(topmost("NDP")))
+            .unwrap();
+        assert!(plan.moves.is_empty());
+    }
+
+    #[test]
+    fn plan_layout_depends_on_crane() {
+        let crates: Crates = "[B]\n[A]\n 1   2   3\n\n".parse().unwrap();
+        let target = Target::Layout(stacks(&["", "AB", ""]));
+
+        let plan = Planner::new(CrateMover9001).plan(&crates, &target).unwrap();
+        assert_eq!(
+            plan.moves,
+            [Move {
+                crates: 2,
+                from: 1,
+                to: 2
+            }]
+        );
+
+        // Moving the pile flips it, so it has to be flipped twice.
+        let plan = Planner::new(CrateMover9000).plan(&crates, &target).unwrap();
+        assert_eq!(plan.moves.len(), 2);
+        assert_eq!(
+            execute(&crates, &plan, CrateMover9000).stacks,
+            stacks(&["", "AB", ""])
+        );
+
+        // With nowhere to put the pile down in between, it can't be flipped back.
+        let crates = Crates {
+            stacks: stacks(&["AB", ""]),
+            labels: vec!["1".into(), "2".into()],
+            instructions: Vec::new(),
+        };
+        let target = Target::Layout(stacks(&["", "AB"]));
+        let error = Planner::new(CrateMover9000).plan(&crates, &target);
+        assert_eq!(error, Err(PlanError::Unreachable));
+    }
+
+    #[test]
+    fn impossible_targets() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        let planner = Planner::new(CrateMover9000);
+        let error = planner.plan(&crates, &Target::Topmost(topmost("XYZ")));
+        assert_eq!(error, Err(PlanError::Unreachable));
+        let error = planner.plan(&crates, &Target::Layout(stacks(&["Z", "", ""])));
+        assert_eq!(error, Err(PlanError::Unreachable));
+
+        let target = Target::Layout(stacks(&["CDMNPZ", "", ""]));
+        let error = planner.with_budget(5).plan(&crates, &target);
+        assert_eq!(error, Err(PlanError::BudgetExhausted { explored: 5 }));
+    }
+
+    /// A few small stacks, and a few moves between them.
+    fn small_crates() -> impl Strategy<Value = Crates> {
+        let name = "[A-C]".prop_map(Crate::from);
+        let stacks = prop::collection::vec(prop::collection::vec(name, 0..3), 3);
+        let moves = prop::collection::vec((0..3usize, 0..3usize, 0..3usize), 0..4);
+        (stacks, moves).prop_map(|(stacks, moves)| {
+            let mut heights: Vec<usize> = stacks.iter().map(Vec::len).collect();
+            let mut instructions = Vec::new();
+            for (from, to, crates) in moves {
+                if heights[from] == 0 {
+                    continue;
+                }
+                let crates = crates % heights[from] + 1;
+                heights[from] -= crates;
+                heights[to] += crates;
+                instructions.push(Move {
+                    crates,
+                    from: from + 1,
+                    to: to + 1,
+                });
+            }
+            Crates {
+                stacks,
+                labels: vec!["1".into(), "2".into(), "3".into()],
+                instructions,
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn plans_are_no_longer_than_known_moves(crates in small_crates()) {
+            let reached = crates.execute_moves(CrateMover9000);
+            let target = Target::Layout(reached.stacks.clone());
+            let plan = Planner::new(CrateMover9000).plan(&crates, &target).unwrap();
+            prop_assert!(plan.moves.len() <= crates.instructions.len());
+            prop_assert_eq!(execute(&crates, &plan, CrateMover9000).stacks, reached.stacks);
+        }
+    }
+}
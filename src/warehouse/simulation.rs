@@ -0,0 +1,237 @@
+use super::{check_move, crane::Crane, move_crates, Crate, Crates, Move, MoveError};
+
+/// The contents of one stack before and after a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackChange {
+    /// 1-based position of the stack.
+    pub stack: usize,
+    pub before: Vec<Crate>,
+    pub after: Vec<Crate>,
+}
+
+/// The effect of a single move.
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// Index of the move in [`Crates::instructions`].
+    pub index: usize,
+    pub inst: Move,
+    /// Every stack the move changed, from left to right.
+    pub changes: Vec<StackChange>,
+}
+
+impl Step {
+    /// 1-based positions of the stacks the move changed.
+    pub fn changed_stacks(&self) -> impl Iterator<Item = usize> + '_ {
+        self.changes.iter().map(|change| change.stack)
+    }
+}
+
+/// Carries out a [`Crates`]' instructions one move at a time, remembering every step so the
+/// simulation can be rewound to any earlier point.
+///
+/// As an iterator, it yields the state after each move until the instructions run out, or until a
+/// move turns out to be illegal.
+#[derive(Debug, Clone)]
+pub struct Simulation<C> {
+    crane: C,
+    crates: Crates,
+    /// Every step computed so far, which may run ahead of `position` after rewinding.
+    steps: Vec<Step>,
+    /// Number of moves applied to `crates.stacks`.
+    position: usize,
+    failed: bool,
+}
+
+impl Crates {
+    pub fn simulate<C: Crane>(&self, crane: C) -> Simulation<C> {
+        Simulation {
+            crane,
+            crates: self.clone(),
+            steps: Vec::new(),
+            position: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<C: Crane> Simulation<C> {
+    /// The number of moves carried out so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The total number of moves.
+    pub fn len(&self) -> usize {
+        self.crates.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.crates.instructions.is_empty()
+    }
+
+    pub fn stacks(&self) -> &[Vec<Crate>] {
+        &self.crates.stacks
+    }
+
+    /// The current stacks, with the moves that haven't been carried out yet.
+    pub fn state(&self) -> Crates {
+        Crates {
+            stacks: self.crates.stacks.clone(),
+            labels: self.crates.labels.clone(),
+            instructions: self.crates.instructions[self.position..].to_vec(),
+        }
+    }
+
+    /// The steps carried out so far, in order.
+    pub fn history(&self) -> &[Step] {
+        &self.steps[..self.position]
+    }
+
+    /// The most recent step, if any moves have been carried out.
+    pub fn last_step(&self) -> Option<&Step> {
+        self.history().last()
+    }
+
+    /// Carry out the next move, returning what it changed, or `None` if there are no moves left.
+    pub fn step(&mut self) -> Option<Result<&Step, MoveError>> {
+        let index = self.position;
+        let inst = self.crates.instructions.get(index)?.clone();
+
+        if index == self.steps.len() {
+            if let Err(reason) = check_move(&self.crates.stacks, &inst) {
+                return Some(Err(MoveError {
+                    index,
+                    inst,
+                    reason,
+                }));
+            }
+            let before = self.crates.stacks.clone();
+            move_crates(&mut self.crates.stacks, &inst, &self.crane);
+            let changes = (0..before.len())
+                .filter(|&i| before[i] != self.crates.stacks[i])
+                .map(|i| StackChange {
+                    stack: i + 1,
+                    before: before[i].clone(),
+                    after: self.crates.stacks[i].clone(),
+                })
+                .collect();
+            self.steps.push(Step {
+                index,
+                inst,
+                changes,
+            });
+        } else {
+            // This step was already computed before rewinding, so replay it.
+            for change in self.steps[index].changes.iter() {
+                self.crates.stacks[change.stack - 1] = change.after.clone();
+            }
+        }
+
+        self.position += 1;
+        Some(Ok(&self.steps[index]))
+    }
+
+    /// Undo moves until only the first `position` have been carried out. Does nothing if fewer
+    /// moves than that have been carried out.
+    pub fn rewind(&mut self, position: usize) {
+        if position < self.position {
+            self.failed = false;
+        }
+        while self.position > position {
+            self.position -= 1;
+            for change in self.steps[self.position].changes.iter() {
+                self.crates.stacks[change.stack - 1] = change.before.clone();
+            }
+        }
+    }
+
+    /// Go forwards or backwards until exactly `position` moves have been carried out.
+    pub fn seek(&mut self, position: usize) -> Result<(), MoveError> {
+        self.rewind(position);
+        while self.position < position.min(self.len()) {
+            if let Some(Err(error)) = self.step() {
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<C: Crane> Iterator for Simulation<C> {
+    type Item = Result<Crates, MoveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.step()?.map(|_| ()) {
+            Ok(()) => Some(Ok(self.state())),
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warehouse::{
+        crane::{CrateMover9000, CrateMover9001},
+        tests::{stacks, EXAMPLE},
+    };
+
+    #[test]
+    fn steps_match_partial_execution() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        let states: Vec<Crates> = crates
+            .simulate(CrateMover9001)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(states.len(), 4);
+        for (i, state) in states.iter().enumerate() {
+            let mut partial = crates.clone();
+            partial.instructions.truncate(i + 1);
+            let expected = partial.execute_moves(CrateMover9001);
+            assert_eq!(state.stacks, expected.stacks);
+            assert_eq!(state.instructions.len(), 3 - i);
+        }
+        assert_eq!(states[3].topmost(), "MCD");
+    }
+
+    #[test]
+    fn rewind_and_replay() {
+        let crates: Crates = EXAMPLE.parse().unwrap();
+        let mut simulation = crates.simulate(CrateMover9000);
+
+        let step = simulation.step().unwrap().unwrap();
+        assert_eq!(step.changed_stacks().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(step.changes[0].before, stacks(&["ZN"])[0]);
+        assert_eq!(step.changes[0].after, stacks(&["ZND"])[0]);
+
+        simulation.seek(4).unwrap();
+        assert_eq!(simulation.state().topmost(), "CMZ");
+        simulation.rewind(1);
+        assert_eq!(simulation.position(), 1);
+        assert_eq!(simulation.stacks(), stacks(&["ZND", "MC", "P"]));
+        simulation.rewind(0);
+        assert_eq!(simulation.stacks(), crates.stacks);
+
+        let replayed: Vec<_> = simulation.by_ref().map(Result::unwrap).collect();
+        assert_eq!(replayed.len(), 4);
+        assert_eq!(simulation.history().len(), 4);
+        assert_eq!(replayed[3].topmost(), "CMZ");
+    }
+
+    #[test]
+    fn stops_at_illegal_move() {
+        let mut crates: Crates = EXAMPLE.parse().unwrap();
+        crates.instructions[1].crates = 5;
+        let mut simulation = crates.simulate(CrateMover9000);
+        assert!(simulation.next().unwrap().is_ok());
+        assert_eq!(simulation.next().unwrap().unwrap_err().index, 1);
+        assert!(simulation.next().is_none());
+        assert_eq!(simulation.position(), 1);
+    }
+}